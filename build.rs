@@ -560,6 +560,15 @@ fn check_target_architecture(interpreter_config: &InterpreterConfig) -> Result<(
     Ok(())
 }
 
+/// Returns the minor version of the rustc used to compile this crate.
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    // e.g. "rustc 1.39.0 (4560ea788 2019-11-04)"
+    version.split('.').nth(1)?.parse().ok()
+}
+
 fn main() -> Result<()> {
     // 1. Setup cfg variables so we can do conditional compilation in this library based on the
     // python interpeter's compilation flags. This is necessary for e.g. matching the right unicode
@@ -633,6 +642,22 @@ fn main() -> Result<()> {
         }
     );
 
+    // 3. Newer std APIs which are only used when the compiler is recent enough.
+    let rustc_minor = rustc_minor_version().unwrap_or(0);
+    for &(minor, cfg) in &[
+        // io::ErrorKind::{Unsupported, OutOfMemory}
+        (54, "rustc_1_54"),
+        // num::IntErrorKind
+        (55, "rustc_1_55"),
+        // std::backtrace
+        (65, "rustc_1_65"),
+    ] {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+        if rustc_minor >= minor {
+            println!("cargo:rustc-cfg={}", cfg);
+        }
+    }
+
     if env::var_os("TARGET") == Some("x86_64-apple-darwin".into()) {
         // TODO: Find out how we can set -undefined dynamic_lookup here (if this is possible)
     }
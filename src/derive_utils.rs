@@ -129,13 +129,15 @@ impl ModuleDef {
     }
     /// Builds a module using user given initializer. Used for `#[pymodule]`.
     ///
+    /// Returns a null pointer with the Python error set if the module can't be built.
+    ///
     /// # Safety
     /// The caller must have GIL.
     pub unsafe fn make_module(
         &'static self,
         doc: &str,
         initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
-    ) -> PyResult<*mut ffi::PyObject> {
+    ) -> *mut ffi::PyObject {
         #[cfg(py_sys_config = "WITH_THREAD")]
         // > Changed in version 3.7: This function is now called by Py_Initialize(), so you don’t have
         // > to call it yourself anymore.
@@ -145,9 +147,21 @@ impl ModuleDef {
         let module = ffi::PyModule_Create(self.0.get());
         let pool = GILPool::new();
         let py = pool.python();
-        if module.is_null() {
-            return Err(crate::PyErr::fetch(py));
+        // The error must be restored while the pool is alive, as restoring requires the GIL
+        // to be marked as held.
+        match self.init_module(py, module, doc, initializer) {
+            Ok(module) => module,
+            Err(e) => e.restore_and_null(py),
         }
+    }
+
+    unsafe fn init_module(
+        &'static self,
+        py: Python,
+        module: *mut ffi::PyObject,
+        doc: &str,
+        initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
+    ) -> PyResult<*mut ffi::PyObject> {
         let module = py.from_owned_ptr_or_err::<PyModule>(module)?;
         module.add("__doc__", doc)?;
         initializer(py, module)?;
@@ -1,19 +1,23 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
-use crate::gil::ensure_gil;
+use crate::class::basic::CompareOp;
+use crate::gil::{self, ensure_gil};
+use crate::once_cell::GILOnceCell;
 use crate::panic::PanicException;
 use crate::type_object::PyTypeObject;
-use crate::types::PyType;
+use crate::types::{PyDict, PyString, PyTuple, PyType};
 use crate::{exceptions, ffi};
 use crate::{
-    AsPyPointer, FromPy, FromPyPointer, IntoPy, IntoPyPointer, Py, PyAny, PyNativeType, PyObject,
-    Python, ToBorrowedObject, ToPyObject,
+    AsPyPointer, AsPyRef, FromPy, FromPyObject, FromPyPointer, IntoPy, IntoPyPointer, Py, PyAny,
+    PyNativeType, PyObject, Python, ToBorrowedObject, ToPyObject,
 };
 use libc::c_int;
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
 use std::io;
 use std::os::raw::c_char;
 use std::ptr::NonNull;
+use std::rc::Rc;
 
 /// Represents a `PyErr` value.
 ///
@@ -27,12 +31,106 @@ pub enum PyErrValue {
     Value(PyObject),
     ToArgs(Box<dyn PyErrArguments>),
     ToObject(Box<dyn ToPyObject>),
+    /// Positional arguments tuple and keyword arguments dict for the exception constructor.
+    ArgsKwargs(PyObject, PyObject),
 }
 
 impl PyErrValue {
+    /// Creates a lazily evaluated value from `value`.
+    ///
+    /// The arguments are evaluated at most once, even if the `PyErr` is copied
+    /// with `clone_ref` before being raised.
     pub fn from_err_args<T: 'static + PyErrArguments>(value: T) -> Self {
         let _ = Python::acquire_gil();
-        PyErrValue::ToArgs(Box::new(value))
+        PyErrValue::ToArgs(Box::new(SharedArguments::new(value)))
+    }
+}
+
+/// `PyErrArguments` which can be shared between copies of a `PyErr`, so that the wrapped
+/// arguments are only evaluated once.
+// `Rc` rather than `Arc`, as `PyErrArguments` isn't `Send` anyway.
+struct SharedArguments(Rc<SharedArgumentsInner>);
+
+struct SharedArgumentsInner {
+    args: Box<dyn PyErrArguments>,
+    value: GILOnceCell<PyResult<PyObject>>,
+}
+
+impl SharedArguments {
+    fn new<T: 'static + PyErrArguments>(value: T) -> Self {
+        SharedArguments(Rc::new(SharedArgumentsInner {
+            args: Box::new(value),
+            value: GILOnceCell::new(),
+        }))
+    }
+}
+
+impl PyErrArguments for SharedArguments {
+    fn arguments(&self, py: Python) -> PyObject {
+        match self.try_arguments(py) {
+            Ok(value) => value,
+            Err(_) => self.0.args.arguments(py),
+        }
+    }
+
+    fn try_arguments(&self, py: Python) -> PyResult<PyObject> {
+        let inner = &*self.0;
+        match inner.value.get_or_init(py, || inner.args.try_arguments(py)) {
+            Ok(value) => Ok(value.clone_ref(py)),
+            Err(err) => Err(err.clone_ref(py)),
+        }
+    }
+
+    fn clone_shared(&self) -> Option<Box<dyn PyErrArguments>> {
+        Some(Box::new(SharedArguments(self.0.clone())))
+    }
+}
+
+/// Best-effort debug output: values are rendered with `repr()`, which needs the GIL.
+/// Must not panic, as it may be used while reporting other failures.
+impl std::fmt::Debug for PyErrValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            PyErrValue::None => f.write_str("None"),
+            PyErrValue::Value(ob) => {
+                let gil = ensure_gil();
+                let py = unsafe { gil.python() };
+                write!(f, "Value({})", repr_or_unavailable(py, ob))
+            }
+            PyErrValue::ToArgs(_) => f.write_str("ToArgs(<lazy args>)"),
+            PyErrValue::ToObject(ob) => {
+                let gil = ensure_gil();
+                let py = unsafe { gil.python() };
+                write!(
+                    f,
+                    "ToObject({})",
+                    repr_or_unavailable(py, &ob.to_object(py))
+                )
+            }
+            PyErrValue::ArgsKwargs(args, kwargs) => {
+                let gil = ensure_gil();
+                let py = unsafe { gil.python() };
+                write!(
+                    f,
+                    "ArgsKwargs({}, {})",
+                    repr_or_unavailable(py, args),
+                    repr_or_unavailable(py, kwargs)
+                )
+            }
+        }
+    }
+}
+
+/// Calls `repr()` on `obj`, swallowing any Python error it raises.
+fn repr_or_unavailable(py: Python, obj: &PyObject) -> String {
+    unsafe {
+        match py.from_owned_ptr_or_opt::<PyString>(ffi::PyObject_Repr(obj.as_ptr())) {
+            Some(repr) => repr.to_string_lossy().into_owned(),
+            None => {
+                ffi::PyErr_Clear();
+                String::from("<unavailable>")
+            }
+        }
     }
 }
 
@@ -50,18 +148,73 @@ pub struct PyErr {
 
     /// The `PyTraceBack` object associated with the error.
     pub ptraceback: Option<PyObject>,
+
+    /// Lazily computed `__cause__` / `__context__` of the exception, used by `Error::source()`.
+    source: GILOnceCell<Option<Box<PyErr>>>,
+
+    /// The Rust backtrace of where the error was created, if it was captured.
+    backtrace: RustBacktrace,
+}
+
+#[cfg(rustc_1_65)]
+type RustBacktrace = Option<Rc<std::backtrace::Backtrace>>;
+#[cfg(not(rustc_1_65))]
+type RustBacktrace = ();
+
+/// Captures a Rust backtrace if they are enabled by `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`.
+#[cfg(rustc_1_65)]
+fn capture_backtrace() -> RustBacktrace {
+    let backtrace = std::backtrace::Backtrace::capture();
+    if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+        Some(Rc::new(backtrace))
+    } else {
+        None
+    }
 }
 
+#[cfg(not(rustc_1_65))]
+fn capture_backtrace() -> RustBacktrace {}
+
 /// Represents the result of a Python call.
 pub type PyResult<T> = Result<T, PyErr>;
 
-/// Marker type that indicates an error while downcasting
-pub struct PyDowncastError;
+/// Error that indicates a failure to downcast a Python object to a specific type.
+pub struct PyDowncastError {
+    from: String,
+    to: &'static str,
+}
+
+impl PyDowncastError {
+    /// Creates a new `PyDowncastError` for a failure to downcast `from` to the type named `to`.
+    pub fn new(from: &PyAny, to: &'static str) -> Self {
+        PyDowncastError {
+            from: from.get_type().name().into_owned(),
+            to,
+        }
+    }
+}
 
 /// Helper conversion trait that allows to use custom arguments for exception constructor.
 pub trait PyErrArguments {
     /// Arguments for exception
     fn arguments(&self, _: Python) -> PyObject;
+
+    /// Arguments for exception, or the error which occurred while building them.
+    ///
+    /// This is used instead of `arguments` when the exception is normalized or restored;
+    /// if it fails, the returned error is raised instead of the exception.
+    /// The default implementation returns `arguments`. Implementations which override this
+    /// method still need to implement `arguments`, e.g. returning a fallback value.
+    fn try_arguments(&self, py: Python) -> PyResult<PyObject> {
+        Ok(self.arguments(py))
+    }
+
+    /// Returns a copy of these arguments which shares their evaluation with `self`, if possible.
+    /// Used by `PyErr::clone_ref` to avoid evaluating the arguments for every copy.
+    #[doc(hidden)]
+    fn clone_shared(&self) -> Option<Box<dyn PyErrArguments>> {
+        None
+    }
 }
 
 impl PyErr {
@@ -99,6 +252,44 @@ impl PyErr {
             ptype: ty.into(),
             pvalue: PyErrValue::ToObject(Box::new(value)),
             ptraceback: None,
+            source: GILOnceCell::new(),
+            backtrace: capture_backtrace(),
+        }
+    }
+
+    /// Creates a new PyErr of type `T` which is constructed as `T(*args, **kwargs)`.
+    ///
+    /// `args` is used as the positional arguments if it is a tuple,
+    /// otherwise it is passed as the only positional argument.
+    /// The exception instance is only created when the error is normalized or restored.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let kwargs = [("code", 42)].into_py_dict(py);
+    /// return Err(PyErr::new_with_kwargs::<MyError, _>("Error message", kwargs));
+    /// ```
+    pub fn new_with_kwargs<T, A>(args: A, kwargs: &PyDict) -> PyErr
+    where
+        T: PyTypeObject,
+        A: ToPyObject,
+    {
+        let py = kwargs.py();
+        let ty = T::type_object(py);
+        assert_ne!(unsafe { ffi::PyExceptionClass_Check(ty.as_ptr()) }, 0);
+
+        let args = args.to_object(py);
+        let args = if unsafe { ffi::PyTuple_Check(args.as_ptr()) } != 0 {
+            args
+        } else {
+            PyTuple::new(py, &[args]).into()
+        };
+
+        PyErr {
+            ptype: ty.into(),
+            pvalue: PyErrValue::ArgsKwargs(args, kwargs.into()),
+            ptraceback: None,
+            source: GILOnceCell::new(),
+            backtrace: capture_backtrace(),
         }
     }
 
@@ -115,6 +306,22 @@ impl PyErr {
             ptype: exc.into(),
             pvalue: PyErrValue::ToObject(Box::new(args)),
             ptraceback: None,
+            source: GILOnceCell::new(),
+            backtrace: capture_backtrace(),
+        }
+    }
+
+    /// Creates a new PyErr of the exception type `exc` with the given `value`.
+    ///
+    /// This is the runtime-typed counterpart of `from_value`, for exception types which are
+    /// only known at runtime. `value` may be any `PyErrValue`, including an existing instance.
+    pub fn from_type_value(exc: &PyType, value: PyErrValue) -> PyErr {
+        PyErr {
+            ptype: exc.into(),
+            pvalue: value,
+            ptraceback: None,
+            source: GILOnceCell::new(),
+            backtrace: capture_backtrace(),
         }
     }
 
@@ -133,6 +340,22 @@ impl PyErr {
             ptype: ty.into(),
             pvalue: value,
             ptraceback: None,
+            source: GILOnceCell::new(),
+            backtrace: capture_backtrace(),
+        }
+    }
+
+    /// Creates an `OSError` with the given `errno`, `strerror` and optional `filename`.
+    ///
+    /// As in Python, the instance is created as the `OSError` subclass matching `errno` (if
+    /// any) when the error is normalized, e.g. `FileNotFoundError` for `ENOENT`.
+    pub fn os_error(py: Python, errno: i32, strerror: &str, filename: Option<&str>) -> PyErr {
+        let ty = py.get_type::<exceptions::OSError>();
+        match filename {
+            Some(filename) => {
+                PyErr::from_type(ty, (errno, strerror.to_string(), filename.to_string()))
+            }
+            None => PyErr::from_type(ty, (errno, strerror.to_string())),
         }
     }
 
@@ -152,28 +375,75 @@ impl PyErr {
                 },
                 pvalue: PyErrValue::Value(obj.into()),
                 ptraceback: None,
+                source: GILOnceCell::new(),
+                backtrace: capture_backtrace(),
             }
         } else if unsafe { ffi::PyExceptionClass_Check(obj.as_ptr()) } != 0 {
             PyErr {
                 ptype: unsafe { Py::from_borrowed_ptr(obj.py(), ptr) },
                 pvalue: PyErrValue::None,
                 ptraceback: None,
+                source: GILOnceCell::new(),
+                backtrace: capture_backtrace(),
             }
         } else {
             PyErr {
                 ptype: exceptions::TypeError::type_object(obj.py()).into(),
                 pvalue: PyErrValue::ToObject(Box::new("exceptions must derive from BaseException")),
                 ptraceback: None,
+                source: GILOnceCell::new(),
+                backtrace: capture_backtrace(),
             }
         }
     }
 
+    /// Creates a new PyErr from `obj` like [from_instance](PyErr::from_instance), with the
+    /// given traceback.
+    ///
+    /// This is useful to re-raise an exception which was caught earlier, passing its
+    /// `__traceback__` so the origin of the exception is not lost.
+    pub fn from_instance_with_traceback(obj: &PyAny, traceback: Option<&PyAny>) -> PyErr {
+        let mut err = PyErr::from_instance(obj);
+        err.ptraceback = traceback.map(PyObject::from);
+        err
+    }
+
     /// Gets whether an error is present in the Python interpreter's global state.
     #[inline]
     pub fn occurred(_: Python) -> bool {
+        debug_assert_gil("PyErr::occurred");
         unsafe { !ffi::PyErr_Occurred().is_null() }
     }
 
+    /// Creates a `UnicodeDecodeError` for `bytes`, which failed to decode as UTF-8 with `err`.
+    ///
+    /// The exception's `start` and `end` span the invalid bytes reported by `err`.
+    /// Fails if the exception could not be created.
+    pub fn unicode_decode_error(
+        py: Python,
+        bytes: &[u8],
+        err: std::str::Utf8Error,
+    ) -> PyResult<PyErr> {
+        let instance = exceptions::UnicodeDecodeError::new_utf8(py, bytes, err)?;
+        Ok(PyErr::from_instance(instance))
+    }
+
+    /// Clears the error currently set in the Python interpreter's global state, if any.
+    ///
+    /// The error is intentionally discarded: unlike `fetch`, this never resumes a Rust panic
+    /// and does not create a `PyErr`. Use it for best-effort cleanup where errors don't matter.
+    #[inline]
+    pub fn clear(_: Python) {
+        debug_assert_gil("PyErr::clear");
+        unsafe { ffi::PyErr_Clear() }
+    }
+
+    /// Intentionally discards the error.
+    ///
+    /// This is the same as dropping it, but makes it explicit that the error is ignored.
+    #[inline]
+    pub fn ignore(self) {}
+
     /// Retrieves the current error from the Python interpreter's global state.
     ///
     /// The error is cleared from the Python interpreter.
@@ -182,30 +452,81 @@ impl PyErr {
     /// If the error fetched is a `PanicException` (which would have originated from a panic in a
     /// pyo3 callback) then this function will resume the panic.
     pub fn fetch(py: Python) -> PyErr {
+        debug_assert_gil("PyErr::fetch");
         unsafe {
             let mut ptype: *mut ffi::PyObject = std::ptr::null_mut();
             let mut pvalue: *mut ffi::PyObject = std::ptr::null_mut();
             let mut ptraceback: *mut ffi::PyObject = std::ptr::null_mut();
             ffi::PyErr_Fetch(&mut ptype, &mut pvalue, &mut ptraceback);
+            PyErr::from_fetched(py, ptype, pvalue, ptraceback)
+        }
+    }
 
-            let err = PyErr::new_from_ffi_tuple(py, ptype, pvalue, ptraceback);
-
-            if ptype == PanicException::type_object(py).as_ptr() {
-                let msg: String = PyAny::from_borrowed_ptr_or_opt(py, pvalue)
-                    .and_then(|obj| obj.extract().ok())
-                    .unwrap_or_else(|| String::from("Unwrapped panic from Python code"));
+    /// Retrieves the current error from the Python interpreter's global state, without
+    /// resuming panics.
+    ///
+    /// The error is cleared from the Python interpreter.
+    /// If no error is set, returns a `SystemError`.
+    ///
+    /// **Caution:** unlike `fetch`, a `PanicException` (which would have originated from a panic
+    /// in a pyo3 callback) is returned like any other error. The caller is responsible for
+    /// propagating it, e.g. by restoring it; swallowing it hides the panic.
+    pub fn fetch_raw(py: Python) -> PyErr {
+        debug_assert_gil("PyErr::fetch_raw");
+        unsafe {
+            let mut ptype: *mut ffi::PyObject = std::ptr::null_mut();
+            let mut pvalue: *mut ffi::PyObject = std::ptr::null_mut();
+            let mut ptraceback: *mut ffi::PyObject = std::ptr::null_mut();
+            ffi::PyErr_Fetch(&mut ptype, &mut pvalue, &mut ptraceback);
+            PyErr::new_from_ffi_tuple(py, ptype, pvalue, ptraceback)
+        }
+    }
 
-                eprintln!(
-                    "--- PyO3 is resuming a panic after fetching a PanicException from Python. ---"
-                );
-                eprintln!("Python stack trace below:");
-                err.print(py);
+    /// Retrieves the current error from the Python interpreter's global state, if any.
+    ///
+    /// Returns `None` if no error is set; otherwise the error is cleared and returned.
+    /// Like `fetch`, a fetched `PanicException` resumes the original panic.
+    pub fn take(py: Python) -> Option<PyErr> {
+        debug_assert_gil("PyErr::take");
+        unsafe {
+            let mut ptype: *mut ffi::PyObject = std::ptr::null_mut();
+            let mut pvalue: *mut ffi::PyObject = std::ptr::null_mut();
+            let mut ptraceback: *mut ffi::PyObject = std::ptr::null_mut();
+            ffi::PyErr_Fetch(&mut ptype, &mut pvalue, &mut ptraceback);
 
-                std::panic::resume_unwind(Box::new(msg))
+            if ptype.is_null() {
+                None
+            } else {
+                Some(PyErr::from_fetched(py, ptype, pvalue, ptraceback))
             }
+        }
+    }
+
+    /// Takes ownership of the values returned by `PyErr_Fetch`,
+    /// resuming the panic if the error is a `PanicException`.
+    unsafe fn from_fetched(
+        py: Python,
+        ptype: *mut ffi::PyObject,
+        pvalue: *mut ffi::PyObject,
+        ptraceback: *mut ffi::PyObject,
+    ) -> PyErr {
+        let err = PyErr::new_from_ffi_tuple(py, ptype, pvalue, ptraceback);
+
+        if ptype == PanicException::type_object(py).as_ptr() {
+            let msg: String = PyAny::from_borrowed_ptr_or_opt(py, pvalue)
+                .and_then(|obj| obj.extract().ok())
+                .unwrap_or_else(|| String::from("Unwrapped panic from Python code"));
 
-            err
+            eprintln!(
+                "--- PyO3 is resuming a panic after fetching a PanicException from Python. ---"
+            );
+            eprintln!("Python stack trace below:");
+            err.print(py);
+
+            std::panic::resume_unwind(Box::new(msg))
         }
+
+        err
     }
 
     /// Creates a new exception type with the given name, which must be of the form
@@ -241,6 +562,33 @@ impl PyErr {
         }
     }
 
+    /// Creates a new exception type with the given name, which must be of the form
+    /// `<module>.<ExceptionName>`.
+    ///
+    /// This is a safe alternative to `new_type`: the new type is returned as a `&PyType`,
+    /// and a failure to create it is returned as an error.
+    ///
+    /// `base` can be an existing exception type to subclass, and `dict` specifies an optional
+    /// dictionary of class variables and methods.
+    pub fn new_exception<'p>(
+        py: Python<'p>,
+        name: &str,
+        base: Option<&PyType>,
+        dict: Option<&PyDict>,
+    ) -> PyResult<&'p PyType> {
+        let name = CString::new(name)?;
+        let base = base.map_or(std::ptr::null_mut(), |base| base.as_ptr());
+        let dict = dict.map_or(std::ptr::null_mut(), |dict| dict.as_ptr());
+
+        unsafe {
+            py.from_owned_ptr_or_err(ffi::PyErr_NewException(
+                name.as_ptr() as *mut c_char,
+                base,
+                dict,
+            ))
+        }
+    }
+
     unsafe fn new_from_ffi_tuple(
         py: Python,
         ptype: *mut ffi::PyObject,
@@ -266,6 +614,8 @@ impl PyErr {
             ptype,
             pvalue,
             ptraceback: PyObject::from_owned_ptr_or_opt(py, ptraceback),
+            source: GILOnceCell::new(),
+            backtrace: RustBacktrace::default(),
         }
     }
 
@@ -282,6 +632,83 @@ impl PyErr {
         unsafe { ffi::PyErr_PrintEx(1) }
     }
 
+    /// Formats the exception and its traceback the same way `print` would write it to
+    /// `sys.stderr`, using Python's `traceback.format_exception`.
+    ///
+    /// Unlike `print`, this does not consume the error.
+    pub fn format_traceback(&self, py: Python) -> PyResult<String> {
+        let mut err = self.clone_ref(py);
+        err.normalize(py);
+
+        let value = match err.pvalue {
+            PyErrValue::Value(ref instance) => instance.clone_ref(py),
+            _ => py.None(),
+        };
+        let traceback = err.ptraceback.unwrap_or_else(|| py.None());
+
+        let lines = py
+            .import("traceback")?
+            .call1("format_exception", (err.ptype, value, traceback))?;
+        let mut formatted = String::new();
+        for line in lines.iter()? {
+            formatted.push_str(line?.extract()?);
+        }
+        Ok(formatted)
+    }
+
+    /// Formats only the `Type: message` summary of the exception, without the traceback,
+    /// using Python's `traceback.format_exception_only`.
+    ///
+    /// Like `format_traceback`, this does not consume the error or write to `sys.stderr`.
+    pub fn one_line(&self, py: Python) -> PyResult<String> {
+        let (ptype, value, _) = self.clone_ref(py).into_normalized_value(py);
+        let lines = py
+            .import("traceback")?
+            .call1("format_exception_only", (ptype, value))?;
+        let mut formatted = String::new();
+        for line in lines.iter()? {
+            formatted.push_str(line?.extract()?);
+        }
+        formatted.truncate(formatted.trim_end().len());
+        Ok(formatted)
+    }
+
+    /// Formats the error as an owned `Type: message` string, e.g. `ValueError: bad input`.
+    ///
+    /// This is the same as the `Display` output: the message is the `str()` of the exception,
+    /// and only the type name is used if it is empty or can't be computed, or if the exception
+    /// instance can't be created. Unlike `one_line`, it doesn't import the `traceback` module, so
+    /// it is cheap enough for routine logging.
+    pub fn capture_message(&self, py: Python) -> String {
+        let ptype = self.ptype.as_ref(py);
+        let type_name = ptype.name();
+        let value = self.value(py);
+        // If normalization failed, `value` is the exception raised while normalizing, whose
+        // message doesn't describe this error.
+        if unsafe { ffi::PyErr_GivenExceptionMatches(value.as_ptr(), ptype.as_ptr()) } == 0 {
+            return type_name.into_owned();
+        }
+        match value.str() {
+            Ok(s) if !s.to_string_lossy().is_empty() => {
+                format!("{}: {}", type_name, s.to_string_lossy())
+            }
+            // Either `str()` failed or it's empty; fall back to just the type name.
+            _ => type_name.into_owned(),
+        }
+    }
+
+    /// Reports the error as unraisable, for use in contexts where it can't be propagated
+    /// (e.g. in a destructor).
+    ///
+    /// This calls `PyErr_WriteUnraisable`, which prints a warning (or calls
+    /// `sys.unraisablehook`) and leaves no error set. `obj` is an optional object
+    /// describing the context in which the error occurred.
+    pub fn write_unraisable(self, py: Python, obj: Option<&PyAny>) {
+        self.restore(py);
+        let obj = obj.map_or(std::ptr::null_mut(), |obj| obj.as_ptr());
+        unsafe { ffi::PyErr_WriteUnraisable(obj) }
+    }
+
     /// Returns true if the current exception matches the exception in `exc`.
     ///
     /// If `exc` is a class object, this also returns `true` when `self` is an instance of a subclass.
@@ -295,14 +722,86 @@ impl PyErr {
         })
     }
 
+    /// Returns true if the current exception matches any of the exception types in `types`.
+    ///
+    /// Each type is checked in turn as `matches` would, stopping at the first match.
+    /// Returns `false` if `types` is empty.
+    pub fn matches_any<'a, I>(&self, _: Python, types: I) -> bool
+    where
+        I: IntoIterator<Item = &'a PyType>,
+    {
+        types.into_iter().any(|exc| unsafe {
+            ffi::PyErr_GivenExceptionMatches(self.ptype.as_ptr(), exc.as_ptr()) != 0
+        })
+    }
+
+    /// Returns the process exit status for this error, as the Python interpreter computes it
+    /// for an exception which reaches the top level.
+    ///
+    /// For a `SystemExit`, this is its `code` if that is an integer, or `0` if it is `None`.
+    /// Other exceptions, and a `SystemExit` with any other `code`, give `1`. Unlike the
+    /// interpreter, this doesn't print anything to `sys.stderr`.
+    pub fn to_exit_code(&self, py: Python) -> i32 {
+        if !self.is_instance::<exceptions::SystemExit>(py) {
+            return 1;
+        }
+        match self.value(py).getattr("code") {
+            Ok(code) if code.is_none() => 0,
+            Ok(code) => code.extract().unwrap_or(1),
+            Err(_) => 1,
+        }
+    }
+
+    /// Returns true if both errors have the same exception type and their `args` are equal.
+    ///
+    /// Both errors are normalized (without being consumed) and their `args` are compared
+    /// with Python's `==`; if the comparison raises an exception, the errors are not equal.
+    pub fn eq_value(&self, py: Python, other: &PyErr) -> bool {
+        let value = self.value(py);
+        let other_value = other.value(py);
+        if value.get_type().as_ptr() != other_value.get_type().as_ptr() {
+            return false;
+        }
+        match (value.getattr("args"), other_value.getattr("args")) {
+            (Ok(args), Ok(other_args)) => args
+                .rich_compare(other_args, CompareOp::Eq)
+                .and_then(PyAny::is_true)
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Returns the index of the first exception type in `types` which the current exception
+    /// matches, like the first matching `except` clause of a `try` statement.
+    ///
+    /// Each type is checked as `matches` would; returns `None` if no type matches.
+    pub fn matched_index(&self, _: Python, types: &[&PyType]) -> Option<usize> {
+        types.iter().position(|exc| unsafe {
+            ffi::PyErr_GivenExceptionMatches(self.ptype.as_ptr(), exc.as_ptr()) != 0
+        })
+    }
+
+    /// Returns true if the type of the exception is exactly `ty`.
+    ///
+    /// Unlike `matches` and `is_instance`, this returns `false` when the exception is an
+    /// instance of a subclass of `ty`.
+    pub fn is_exact_type(&self, _: Python, ty: &PyType) -> bool {
+        self.ptype.as_ptr() == ty.as_ptr()
+    }
+
     /// Returns true if the current exception is instance of `T`.
     pub fn is_instance<T>(&self, py: Python) -> bool
     where
         T: PyTypeObject,
     {
-        unsafe {
-            ffi::PyErr_GivenExceptionMatches(self.ptype.as_ptr(), T::type_object(py).as_ptr()) != 0
-        }
+        self.is_instance_of(py, T::type_object(py))
+    }
+
+    /// Returns true if the current exception is instance of `ty`.
+    ///
+    /// This is the same as `is_instance`, for exception types which are only known at runtime.
+    pub fn is_instance_of(&self, _: Python, ty: &PyType) -> bool {
+        unsafe { ffi::PyErr_GivenExceptionMatches(self.ptype.as_ptr(), ty.as_ptr()) != 0 }
     }
 
     /// Normalizes the error. This ensures that the exception value is an instance
@@ -319,25 +818,53 @@ impl PyErr {
     /// Helper function for normalizing the error by deconstructing and reconstructing the `PyErr`.
     /// Must not panic for safety in `normalize()`.
     fn into_normalized(self, py: Python) -> PyErr {
+        let backtrace = self.backtrace.clone();
+        let (mut ptype, mut pvalue, mut ptraceback) = self.into_ffi_tuple(py);
+        let mut err = unsafe {
+            ffi::PyErr_NormalizeException(&mut ptype, &mut pvalue, &mut ptraceback);
+            PyErr::new_from_ffi_tuple(py, ptype, pvalue, ptraceback)
+        };
+        err.backtrace = backtrace;
+        err
+    }
+
+    /// Returns the exception instance if the error is already normalized, or `None` otherwise.
+    ///
+    /// Unlike `value`, this borrows the instance without normalizing or copying the error;
+    /// call `normalize` first to make sure the instance exists.
+    pub fn instance_ref<'p>(&'p self, py: Python<'p>) -> Option<&'p PyAny> {
+        match self.pvalue {
+            PyErrValue::Value(ref instance) => Some(instance.as_ref(py)),
+            _ => None,
+        }
+    }
+
+    /// Normalizes the error and returns its type, instance and traceback.
+    ///
+    /// This consumes the error, so the error is normalized exactly once; prefer it over
+    /// repeated calls to `value` or `args` when the parts of the error are needed.
+    pub fn into_normalized_value(self, py: Python) -> (Py<PyType>, PyObject, Option<PyObject>) {
         let PyErr {
             ptype,
             pvalue,
             ptraceback,
-        } = self;
-
-        let mut pvalue = match pvalue {
-            PyErrValue::None => std::ptr::null_mut(),
-            PyErrValue::Value(ob) => ob.into_ptr(),
-            PyErrValue::ToArgs(ob) => ob.arguments(py).into_ptr(),
-            PyErrValue::ToObject(ob) => ob.to_object(py).into_ptr(),
+            ..
+        } = self.into_normalized(py);
+        let instance = match pvalue {
+            PyErrValue::Value(instance) => instance,
+            _ => py.None(),
         };
+        (ptype, instance, ptraceback)
+    }
 
-        let mut ptype = ptype.into_ptr();
-        let mut ptraceback = ptraceback.into_ptr();
-        unsafe {
-            ffi::PyErr_NormalizeException(&mut ptype, &mut pvalue, &mut ptraceback);
-            PyErr::new_from_ffi_tuple(py, ptype, pvalue, ptraceback)
-        }
+    /// Normalizes the error and decomposes it into its type, instance and traceback.
+    ///
+    /// This is the same as [into_normalized_value](PyErr::into_normalized_value), with the type
+    /// returned as a plain `PyObject`, which is convenient when handing the parts to C APIs.
+    /// It is the inverse of `PyErr_Restore` followed by `PyErr::fetch`.
+    pub fn into_parts(self, py: Python) -> (PyObject, PyObject, Option<PyObject>) {
+        let (ptype, instance, ptraceback) = self.into_normalized_value(py);
+        (ptype.into(), instance, ptraceback)
     }
 
     /// Retrieves the exception instance for this error.
@@ -347,66 +874,438 @@ impl PyErr {
     fn instance(mut self, py: Python) -> PyObject {
         self.normalize(py);
         match self.pvalue {
-            PyErrValue::Value(ref instance) => instance.clone_ref(py),
+            PyErrValue::Value(ref instance) => {
+                // Keep the traceback on the instance, so it is not lost when the instance is
+                // raised again from Python.
+                if let Some(ref traceback) = self.ptraceback {
+                    unsafe {
+                        if ffi::PyException_SetTraceback(instance.as_ptr(), traceback.as_ptr())
+                            == -1
+                        {
+                            // Not a traceback object; leave `__traceback__` as it is.
+                            ffi::PyErr_Clear();
+                        }
+                    }
+                }
+                instance.clone_ref(py)
+            }
             _ => py.None(),
         }
     }
 
-    /// Writes the error back to the Python interpreter's global state.
-    /// This is the opposite of `PyErr::fetch()`.
-    #[inline]
-    pub fn restore(self, py: Python) {
-        let PyErr {
-            ptype,
-            pvalue,
-            ptraceback,
-        } = self;
+    /// Returns the exception instance for this error, without consuming the `PyErr`.
+    ///
+    /// If the error has not been normalized yet, the instance is created from a copy of the
+    /// error, so `self` is left as it is.
+    pub fn value<'p>(&self, py: Python<'p>) -> &'p PyAny {
+        let instance = self.clone_ref(py).instance(py);
+        unsafe { py.from_owned_ptr(instance.into_ptr()) }
+    }
 
-        let pvalue = match pvalue {
-            PyErrValue::None => std::ptr::null_mut(),
-            PyErrValue::Value(ob) => ob.into_ptr(),
-            PyErrValue::ToArgs(ob) => ob.arguments(py).into_ptr(),
-            PyErrValue::ToObject(ob) => ob.to_object(py).into_ptr(),
-        };
-        unsafe { ffi::PyErr_Restore(ptype.into_ptr(), pvalue, ptraceback.into_ptr()) }
+    /// Replaces the traceback of this error, which is used when the error is restored.
+    ///
+    /// `None` removes the traceback.
+    ///
+    /// Example:
+    /// ```ignore
+    /// err.with_traceback(Some(traceback)).restore(py);
+    /// ```
+    pub fn with_traceback(mut self, traceback: Option<PyObject>) -> PyErr {
+        self.ptraceback = traceback;
+        self
     }
 
-    /// Utility method for proc-macro code
-    #[doc(hidden)]
-    pub fn restore_and_null<T>(self, py: Python) -> *mut T {
-        self.restore(py);
-        std::ptr::null_mut()
+    /// Returns the value which will be passed to the exception type when the error is
+    /// normalized, without normalizing the error.
+    ///
+    /// This is the evaluated arguments for `PyErrValue::ToArgs` and `PyErrValue::ToObject`, the
+    /// instance for `PyErrValue::Value`, the positional arguments for `PyErrValue::ArgsKwargs`,
+    /// and `None` for `PyErrValue::None`.
+    pub fn pending_args(&self, py: Python) -> PyObject {
+        match self.pvalue {
+            PyErrValue::None => py.None(),
+            PyErrValue::Value(ref ob) => ob.clone_ref(py),
+            PyErrValue::ToArgs(ref ob) => ob.arguments(py),
+            PyErrValue::ToObject(ref ob) => ob.to_object(py),
+            PyErrValue::ArgsKwargs(ref args, _) => args.clone_ref(py),
+        }
     }
 
-    /// Utility method for proc-macro code
-    #[doc(hidden)]
-    pub fn restore_and_minus1(self, py: Python) -> crate::libc::c_int {
-        self.restore(py);
-        -1
+    /// Returns whether the error carries a value, i.e. whether its value is not `PyErrValue::None`.
+    ///
+    /// This doesn't need the GIL and doesn't normalize the error.
+    pub fn has_value(&self) -> bool {
+        match self.pvalue {
+            PyErrValue::None => false,
+            PyErrValue::Value(_)
+            | PyErrValue::ToArgs(_)
+            | PyErrValue::ToObject(_)
+            | PyErrValue::ArgsKwargs(_, _) => true,
+        }
     }
 
-    /// Issues a warning message.
-    /// May return a `PyErr` if warnings-as-errors is enabled.
-    pub fn warn(py: Python, category: &PyAny, message: &str, stacklevel: i32) -> PyResult<()> {
-        let message = CString::new(message)?;
-        unsafe {
+    /// Returns the Rust backtrace of where the error was created.
+    ///
+    /// Like `std::backtrace::Backtrace::capture`, a backtrace is only captured when enabled by
+    /// the `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables, and only for errors
+    /// created from Rust: errors fetched from the interpreter never have one.
+    /// Use [with_rust_backtrace](PyErr::with_rust_backtrace) to capture one regardless.
+    ///
+    /// Requires Rust 1.65 or later.
+    #[cfg(rustc_1_65)]
+    pub fn rust_backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        self.backtrace.as_deref()
+    }
+
+    /// Captures the current Rust backtrace and attaches it to the error, replacing any
+    /// backtrace it already had.
+    ///
+    /// Requires Rust 1.65 or later.
+    #[cfg(rustc_1_65)]
+    pub fn with_rust_backtrace(mut self) -> PyErr {
+        self.backtrace = Some(Rc::new(std::backtrace::Backtrace::force_capture()));
+        self
+    }
+
+    /// Returns the `errno` attribute of an `OSError`.
+    ///
+    /// Returns `None` if the exception is not an `OSError` or has no error number.
+    pub fn errno(&self, py: Python) -> Option<i32> {
+        self.os_error_attr(py, "errno")
+    }
+
+    /// Returns the `strerror` attribute of an `OSError`.
+    ///
+    /// Returns `None` if the exception is not an `OSError` or has no error message.
+    pub fn strerror(&self, py: Python) -> Option<String> {
+        self.os_error_attr(py, "strerror")
+    }
+
+    /// Returns the `filename` attribute of an `OSError`.
+    ///
+    /// Returns `None` if the exception is not an `OSError` or has no (string) file name.
+    pub fn filename(&self, py: Python) -> Option<String> {
+        self.os_error_attr(py, "filename")
+    }
+
+    fn os_error_attr<T>(&self, py: Python, name: &str) -> Option<T>
+    where
+        T: for<'p> FromPyObject<'p>,
+    {
+        if !self.is_instance::<exceptions::OSError>(py) {
+            return None;
+        }
+        self.value(py).getattr(name).ok()?.extract().ok()
+    }
+
+    /// Returns the `__name__` of the exception type, e.g. `"ValueError"`.
+    ///
+    /// This is the unqualified class name: it includes neither the module nor any enclosing
+    /// classes (`__qualname__`). The error is not normalized, so this is the type the error was
+    /// created with.
+    pub fn type_name(&self, py: Python) -> PyResult<String> {
+        self.ptype.as_ref(py).getattr("__name__")?.extract()
+    }
+
+    /// Returns the traceback of this error, if it has one.
+    pub fn traceback<'p>(&'p self, py: Python<'p>) -> Option<&'p PyAny> {
+        self.ptraceback.as_ref().map(|tb| tb.as_ref(py))
+    }
+
+    /// Returns the `args` tuple of the exception instance.
+    ///
+    /// This is equivalent to the Python expression `self.args`.
+    pub fn args<'p>(&self, py: Python<'p>) -> PyResult<&'p PyTuple> {
+        self.value(py)
+            .getattr("args")?
+            .downcast()
+            .map_err(|_| exceptions::TypeError::py_err("exception args is not a tuple"))
+    }
+
+    /// Returns the cause (either an exception instance, or `None`) set by `raise ... from ...`.
+    ///
+    /// This is equivalent to the Python expression `self.__cause__`.
+    pub fn cause(&self, py: Python) -> Option<PyErr> {
+        let instance = self.value(py);
+        let cause = unsafe {
+            PyObject::from_owned_ptr_or_opt(py, ffi::PyException_GetCause(instance.as_ptr()))
+        };
+        cause.map(|obj| PyErr::from_instance(obj.as_ref(py)))
+    }
+
+    /// Returns the chain of implicit `__context__` exceptions of this error.
+    ///
+    /// The first element is the exception that was being handled when this exception was
+    /// raised, the second one is its context, and so on until an exception without a context.
+    /// If the chain contains a cycle, it stops before the first exception which was already
+    /// seen.
+    pub fn context_chain(&self, py: Python) -> PyResult<Vec<PyErr>> {
+        let mut chain = Vec::new();
+        let mut current: PyObject = self.value(py).into();
+        let mut seen = HashSet::new();
+        seen.insert(current.as_ptr());
+        loop {
+            let context = unsafe {
+                PyObject::from_owned_ptr_or_opt(py, ffi::PyException_GetContext(current.as_ptr()))
+            };
+            match context {
+                Some(context) if seen.insert(context.as_ptr()) => {
+                    chain.push(PyErr::from_instance(context.as_ref(py)));
+                    current = context;
+                }
+                _ => return Ok(chain),
+            }
+        }
+    }
+
+    /// Sets the cause associated with the exception, pass `None` to clear it.
+    ///
+    /// This is equivalent to `raise self from cause` in Python; setting a cause also sets
+    /// `__suppress_context__`, and clearing it unsets `__suppress_context__` again.
+    ///
+    /// Fails if `__suppress_context__` can't be reset.
+    pub fn set_cause(&mut self, py: Python, cause: Option<PyErr>) -> PyResult<()> {
+        self.normalize(py);
+        // The cached `Error::source()` may be stale now.
+        self.source = GILOnceCell::new();
+
+        if let PyErrValue::Value(ref instance) = self.pvalue {
+            let suppress_context = cause.is_some();
+            let cause = cause.map(|err| err.instance(py));
+            unsafe {
+                // PyException_SetCause steals the reference to the cause.
+                ffi::PyException_SetCause(instance.as_ptr(), cause.into_ptr());
+            }
+            if !suppress_context {
+                instance.as_ref(py).setattr("__suppress_context__", false)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the context associated with the exception, pass `None` to clear it.
+    ///
+    /// This is the exception that was being handled when this exception was raised, as set
+    /// implicitly by Python. Unlike `set_cause`, `__suppress_context__` is left unchanged.
+    pub fn set_context(&mut self, py: Python, context: Option<PyErr>) {
+        self.normalize(py);
+        // The cached `Error::source()` may be stale now.
+        self.source = GILOnceCell::new();
+
+        if let PyErrValue::Value(ref instance) = self.pvalue {
+            let context = context.map(|err| err.instance(py));
+            unsafe {
+                // PyException_SetContext steals the reference to the context.
+                ffi::PyException_SetContext(instance.as_ptr(), context.into_ptr());
+            }
+        }
+    }
+
+    /// Attaches a note to the exception, as by `BaseException.add_note`.
+    ///
+    /// Notes accumulate in the instance's `__notes__` list. Exception notes were added in
+    /// Python 3.11; on older interpreters this returns a `NotImplementedError` and leaves the
+    /// exception unchanged.
+    pub fn add_note(&mut self, py: Python, note: &str) -> PyResult<()> {
+        self.normalize(py);
+
+        if let PyErrValue::Value(ref instance) = self.pvalue {
+            let instance = instance.as_ref(py);
+            if !instance.hasattr("add_note")? {
+                return Err(exceptions::NotImplementedError::py_err(
+                    "exception notes require Python 3.11 or later",
+                ));
+            }
+            instance.call_method1("add_note", (note,))?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` with the currently set error (if any) saved away, and restores it afterwards.
+    ///
+    /// This is the `PyErr_Fetch` / `PyErr_Restore` bracket CPython uses around cleanup code:
+    /// `f` runs with no error set and may raise and clear errors of its own. Any error
+    /// still set when `f` returns is replaced by the saved one.
+    pub fn with_saved<R>(py: Python, f: impl FnOnce(Python) -> R) -> R {
+        debug_assert_gil("PyErr::with_saved");
+        let mut ptype: *mut ffi::PyObject = std::ptr::null_mut();
+        let mut pvalue: *mut ffi::PyObject = std::ptr::null_mut();
+        let mut ptraceback: *mut ffi::PyObject = std::ptr::null_mut();
+        unsafe { ffi::PyErr_Fetch(&mut ptype, &mut pvalue, &mut ptraceback) };
+
+        let result = f(py);
+
+        // PyErr_Restore steals the references and clears any error left set by `f`.
+        unsafe { ffi::PyErr_Restore(ptype, pvalue, ptraceback) };
+        result
+    }
+
+    /// Writes the error back to the Python interpreter's global state.
+    /// This is the opposite of `PyErr::fetch()`.
+    #[inline]
+    pub fn restore(self, py: Python) {
+        debug_assert_gil("PyErr::restore");
+        let (ptype, pvalue, ptraceback) = self.into_ffi_tuple(py);
+        unsafe { ffi::PyErr_Restore(ptype, pvalue, ptraceback) }
+    }
+
+    /// Writes the error back to the Python interpreter's global state like `restore`, and
+    /// returns whether the restored error is a `PanicException`.
+    ///
+    /// This is the counterpart of the `PanicException` handling in `fetch`: it lets code at an
+    /// FFI boundary tell whether it is propagating a Rust panic.
+    pub fn restore_checked(self, py: Python) -> bool {
+        debug_assert_gil("PyErr::restore_checked");
+        let (ptype, pvalue, ptraceback) = self.into_ffi_tuple(py);
+        let is_panic = ptype == PanicException::type_object(py).as_ptr();
+        unsafe { ffi::PyErr_Restore(ptype, pvalue, ptraceback) };
+        is_panic
+    }
+
+    /// Writes the error back to the Python interpreter's global state, unless an error is
+    /// already set there.
+    ///
+    /// Returns whether the error was restored; otherwise it is dropped, so the error which was
+    /// already set is not overwritten.
+    pub fn restore_if_unset(self, py: Python) -> bool {
+        if PyErr::occurred(py) {
+            false
+        } else {
+            self.restore(py);
+            true
+        }
+    }
+
+    /// Converts the error into owned `(type, value, traceback)` pointers,
+    /// as expected by `PyErr_Restore` and `PyErr_NormalizeException`.
+    ///
+    /// If building the exception's arguments fails, or the exception has to be constructed with
+    /// keyword arguments and the constructor raises, that error is returned instead.
+    /// Must not panic for safety in `normalize()`.
+    fn into_ffi_tuple(
+        self,
+        py: Python,
+    ) -> (*mut ffi::PyObject, *mut ffi::PyObject, *mut ffi::PyObject) {
+        let PyErr {
+            ptype,
+            pvalue,
+            ptraceback,
+            ..
+        } = self;
+
+        let pvalue = match pvalue {
+            PyErrValue::None => std::ptr::null_mut(),
+            PyErrValue::Value(ob) => ob.into_ptr(),
+            PyErrValue::ToArgs(ob) => match ob.try_arguments(py) {
+                Ok(args) => args.into_ptr(),
+                // Raise the error which occurred while building the arguments instead.
+                Err(err) => return err.into_ffi_tuple(py),
+            },
+            PyErrValue::ToObject(ob) => ob.to_object(py).into_ptr(),
+            PyErrValue::ArgsKwargs(args, kwargs) => unsafe {
+                let instance = ffi::PyObject_Call(ptype.as_ptr(), args.as_ptr(), kwargs.as_ptr());
+                if instance.is_null() {
+                    let mut ptype: *mut ffi::PyObject = std::ptr::null_mut();
+                    let mut pvalue: *mut ffi::PyObject = std::ptr::null_mut();
+                    let mut ptraceback: *mut ffi::PyObject = std::ptr::null_mut();
+                    ffi::PyErr_Fetch(&mut ptype, &mut pvalue, &mut ptraceback);
+                    return (ptype, pvalue, ptraceback);
+                }
+                instance
+            },
+        };
+        (ptype.into_ptr(), pvalue, ptraceback.into_ptr())
+    }
+
+    /// Returns `Err(self)`.
+    ///
+    /// This is a shorthand for returning the error from a function, which unlike `.into()`
+    /// doesn't need a type annotation to pick the conversion:
+    /// ```ignore
+    /// return exceptions::ValueError::py_err("Error message").err();
+    /// ```
+    #[inline]
+    pub fn err<T>(self) -> PyResult<T> {
+        Err(self)
+    }
+
+    /// Utility method for proc-macro code
+    #[doc(hidden)]
+    pub fn restore_and_null<T>(self, py: Python) -> *mut T {
+        self.restore(py);
+        std::ptr::null_mut()
+    }
+
+    /// Converts `result` to the return value convention of the Python C API.
+    ///
+    /// Returns a new reference to the object on `Ok`; on `Err` the error is restored and
+    /// a null pointer is returned.
+    pub fn from_result<T>(py: Python, result: PyResult<T>) -> *mut ffi::PyObject
+    where
+        T: ToPyObject,
+    {
+        match result {
+            Ok(value) => value.to_object(py).into_ptr(),
+            Err(err) => err.restore_and_null(py),
+        }
+    }
+
+    /// Utility method for proc-macro code
+    #[doc(hidden)]
+    pub fn restore_and_minus1(self, py: Python) -> crate::libc::c_int {
+        self.restore(py);
+        -1
+    }
+
+    /// Issues a warning message.
+    ///
+    /// `stacklevel` is the stack frame the warning is attributed to, as in Python's
+    /// `warnings.warn`: 1 is the function calling `warn`, 2 is its caller, and so on.
+    /// It must be at least 1, otherwise a `ValueError` is returned.
+    /// May return a `PyErr` if warnings-as-errors is enabled.
+    pub fn warn(py: Python, category: &PyAny, message: &str, stacklevel: usize) -> PyResult<()> {
+        if stacklevel < 1 {
+            return Err(exceptions::ValueError::py_err(
+                "warning stacklevel must be at least 1",
+            ));
+        }
+        let stacklevel = <ffi::Py_ssize_t as std::convert::TryFrom<_>>::try_from(stacklevel)?;
+        let message = CString::new(message)?;
+        unsafe {
             error_on_minusone(
                 py,
-                ffi::PyErr_WarnEx(
-                    category.as_ptr(),
-                    message.as_ptr(),
-                    stacklevel as ffi::Py_ssize_t,
-                ),
+                ffi::PyErr_WarnEx(category.as_ptr(), message.as_ptr(), stacklevel),
             )
         }
     }
 
+    /// Issues a warning message of category `T`, e.g. `exceptions::DeprecationWarning`.
+    ///
+    /// This is the same as [warn](PyErr::warn) with `T`'s type object as the category.
+    /// May return a `PyErr` if warnings-as-errors is enabled.
+    pub fn warn_category<T>(py: Python, message: &str, stacklevel: usize) -> PyResult<()>
+    where
+        T: PyTypeObject,
+    {
+        PyErr::warn(py, T::type_object(py), message, stacklevel)
+    }
+
     pub fn clone_ref(&self, py: Python) -> PyErr {
         let v = match self.pvalue {
             PyErrValue::None => PyErrValue::None,
             PyErrValue::Value(ref ob) => PyErrValue::Value(ob.clone_ref(py)),
-            PyErrValue::ToArgs(ref ob) => PyErrValue::Value(ob.arguments(py)),
+            PyErrValue::ToArgs(ref ob) => match ob.clone_shared() {
+                Some(shared) => PyErrValue::ToArgs(shared),
+                // If building the arguments fails, fall back to `arguments` so that the copy
+                // keeps the original exception type.
+                None => {
+                    PyErrValue::Value(ob.try_arguments(py).unwrap_or_else(|_| ob.arguments(py)))
+                }
+            },
             PyErrValue::ToObject(ref ob) => PyErrValue::Value(ob.to_object(py)),
+            PyErrValue::ArgsKwargs(ref args, ref kwargs) => {
+                PyErrValue::ArgsKwargs(args.clone_ref(py), kwargs.clone_ref(py))
+            }
         };
 
         let t = if let Some(ref val) = self.ptraceback {
@@ -418,16 +1317,100 @@ impl PyErr {
             ptype: self.ptype.clone_ref(py),
             pvalue: v,
             ptraceback: t,
+            source: GILOnceCell::new(),
+            backtrace: self.backtrace.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl PyErr {
+    /// Converts the error into an `anyhow::Error`.
+    ///
+    /// The error's message is the exception's `Display` output. If the exception has a
+    /// traceback, the formatted traceback is included as the source of the error.
+    ///
+    /// (`anyhow` already converts every `Send + Sync` error type with `From`, which rules out a
+    /// `From<PyErr>` implementation; use `.map_err(PyErr::into_anyhow)?` instead.)
+    pub fn into_anyhow(self) -> anyhow::Error {
+        let gil = ensure_gil();
+        let py = unsafe { gil.python() };
+
+        let message = self.to_string();
+        match self.ptraceback {
+            Some(_) => match self.format_traceback(py) {
+                Ok(traceback) => anyhow::Error::msg(traceback).context(message),
+                Err(_) => anyhow::Error::msg(message),
+            },
+            None => anyhow::Error::msg(message),
         }
     }
 }
 
+/// Cloning a `PyErr` acquires the GIL if it is not already held, so it is not free;
+/// use [`clone_ref`](PyErr::clone_ref) when a `Python` token is available.
+impl Clone for PyErr {
+    fn clone(&self) -> Self {
+        let gil = ensure_gil();
+        let py = unsafe { gil.python() };
+        self.clone_ref(py)
+    }
+}
+
 impl std::fmt::Debug for PyErr {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        f.write_str(format!("PyErr {{ type: {:?} }}", self.ptype).as_str())
+        f.write_str(
+            format!(
+                "PyErr {{ type: {:?}, value: {:?} }}",
+                self.ptype, self.pvalue
+            )
+            .as_str(),
+        )
+    }
+}
+
+/// Renders the error the same way Python does on the last line of a traceback,
+/// e.g. `ValueError: invalid literal`.
+impl std::fmt::Display for PyErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let gil = ensure_gil();
+        let py = unsafe { gil.python() };
+        f.write_str(&self.capture_message(py))
+    }
+}
+
+impl std::error::Error for PyErr {
+    /// Returns the exception's `__cause__`, or its `__context__` if no cause was set
+    /// and the context is not suppressed.
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        let gil = ensure_gil();
+        let py = unsafe { gil.python() };
+
+        self.source
+            .get_or_init(py, || chained_exception(self.value(py)).map(Box::new))
+            .as_ref()
+            .map(|err| err.as_ref() as &(dyn std::error::Error + 'static))
     }
 }
 
+/// Looks up the exception that `instance` was chained from, following Python's rules for
+/// which of `__cause__` and `__context__` gets displayed.
+fn chained_exception(instance: &PyAny) -> Option<PyErr> {
+    let attr = |name| instance.getattr(name).ok().filter(|obj| !obj.is_none());
+
+    let chained = attr("__cause__").or_else(|| {
+        let suppress_context = attr("__suppress_context__")
+            .and_then(|obj| obj.is_true().ok())
+            .unwrap_or(false);
+        if suppress_context {
+            None
+        } else {
+            attr("__context__")
+        }
+    });
+    chained.map(PyErr::from_instance)
+}
+
 impl FromPy<PyErr> for PyObject {
     fn from_py(other: PyErr, py: Python) -> Self {
         other.instance(py)
@@ -450,14 +1433,23 @@ impl<'a> IntoPy<PyObject> for &'a PyErr {
 
 /// Convert `PyDowncastError` to Python `TypeError`.
 impl std::convert::From<PyDowncastError> for PyErr {
-    fn from(_err: PyDowncastError) -> PyErr {
-        exceptions::TypeError.into()
+    fn from(err: PyDowncastError) -> PyErr {
+        exceptions::TypeError::py_err(err.to_string())
+    }
+}
+
+impl std::fmt::Debug for PyDowncastError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.debug_struct("PyDowncastError")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish()
     }
 }
 
-impl<'p> std::fmt::Debug for PyDowncastError {
+impl std::fmt::Display for PyDowncastError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        f.write_str("PyDowncastError")
+        write!(f, "expected {}, got {}", self.to, self.from)
     }
 }
 
@@ -478,11 +1470,269 @@ impl<T> std::convert::Into<PyResult<T>> for PyErr {
     }
 }
 
+/// Conversion of a Rust error into an instance of a custom Python exception.
+///
+/// Implementing this trait provides `From<T> for PyErr`, so the `?` operator raises the
+/// exception returned by `to_exception`, e.g. an instance of a `create_exception!` type with
+/// attributes describing the error:
+///
+/// ```ignore
+/// create_exception!(mymodule, ParseFailure, pyo3::exceptions::Exception);
+///
+/// impl PyErrException for ParseError {
+///     fn to_exception(self, py: Python) -> PyResult<PyObject> {
+///         let exception = py.get_type::<ParseFailure>().call1((self.to_string(),))?;
+///         exception.setattr("line", self.line)?;
+///         Ok(exception.into())
+///     }
+/// }
+/// ```
+///
+/// If `to_exception` fails, or doesn't return an exception instance, the resulting `PyErr`
+/// is the error that occurred or a `TypeError`, as with `PyErr::from_instance`.
+pub trait PyErrException {
+    /// Creates the exception instance which is raised for this error.
+    fn to_exception(self, py: Python) -> PyResult<PyObject>;
+}
+
+impl<T> std::convert::From<T> for PyErr
+where
+    T: PyErrException,
+{
+    fn from(err: T) -> PyErr {
+        let gil = ensure_gil();
+        let py = unsafe { gil.python() };
+        match err.to_exception(py) {
+            Ok(exception) => PyErr::from_instance(exception.as_ref(py)),
+            Err(err) => err,
+        }
+    }
+}
+
+/// Extension methods for `PyResult`.
+pub trait PyResultExt {
+    /// Replaces an error for which `predicate` returns true with an error of type `T`.
+    ///
+    /// The new error has the same message (the `str()` of the original exception), and the
+    /// original error is set as its `__cause__`. Other errors and `Ok` values are returned
+    /// unchanged.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let value = lookup(key).map_pyerr_type::<ValueError, _>(py, |err| {
+    ///     err.is_instance::<KeyError>(py)
+    /// })?;
+    /// ```
+    fn map_pyerr_type<T, F>(self, py: Python, predicate: F) -> Self
+    where
+        T: PyTypeObject,
+        F: FnOnce(&PyErr) -> bool;
+}
+
+impl<V> PyResultExt for PyResult<V> {
+    fn map_pyerr_type<T, F>(self, py: Python, predicate: F) -> Self
+    where
+        T: PyTypeObject,
+        F: FnOnce(&PyErr) -> bool,
+    {
+        match self {
+            Err(err) if predicate(&err) => {
+                let message = match err.value(py).str() {
+                    Ok(message) => message.to_string_lossy().into_owned(),
+                    Err(_) => String::new(),
+                };
+                let mut mapped = PyErr::from_type(T::type_object(py), message);
+                mapped.set_cause(py, Some(err))?;
+                Err(mapped)
+            }
+            other => other,
+        }
+    }
+}
+
+/// A snapshot of a `PyErr` which can be sent to other threads.
+///
+/// `PyErr` refers to Python objects, so it can't be used without the GIL. `SendPyErr` instead
+/// formats the exception's type name, message and traceback into strings when it is created.
+/// This is lossy: the error can only be rebuilt as a `RuntimeError` with the same message.
+#[derive(Debug, Clone)]
+pub struct SendPyErr {
+    type_name: String,
+    message: String,
+    traceback: Option<String>,
+}
+
+impl SendPyErr {
+    /// Captures `err`.
+    pub fn new(py: Python, err: &PyErr) -> SendPyErr {
+        let message = match err.value(py).str() {
+            Ok(s) => s.to_string_lossy().into_owned(),
+            Err(_) => String::new(),
+        };
+        let traceback = match err.ptraceback {
+            Some(_) => err.format_traceback(py).ok(),
+            None => None,
+        };
+        SendPyErr {
+            type_name: err.ptype.as_ref(py).name().into_owned(),
+            message,
+            traceback,
+        }
+    }
+
+    /// Returns the name of the exception type, e.g. `ValueError`.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns `str()` of the exception, or an empty string if that failed.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the formatted traceback, if the exception had one.
+    pub fn traceback(&self) -> Option<&str> {
+        self.traceback.as_ref().map(|traceback| &traceback[..])
+    }
+
+    /// Rebuilds the error as a `RuntimeError`, whose message is formatted like `PyErr`'s
+    /// `Display` output, e.g. `ValueError: message`.
+    pub fn into_pyerr(self, _: Python) -> PyErr {
+        exceptions::RuntimeError::py_err(self.to_string())
+    }
+}
+
+impl std::fmt::Display for SendPyErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.message.is_empty() {
+            f.write_str(&self.type_name)
+        } else {
+            write!(f, "{}: {}", self.type_name, self.message)
+        }
+    }
+}
+
+impl std::error::Error for SendPyErr {}
+
+/// Builder for a family of custom exception types which share a module.
+///
+/// Example:
+/// ```ignore
+/// let types = ExceptionHierarchy::new()
+///     .base("ParseError")
+///     .subclass("SyntaxError", "ParseError")
+///     .subclass("UnexpectedToken", "SyntaxError")
+///     .build(py, "mymodule")?;
+/// module.add("UnexpectedToken", types["UnexpectedToken"])?;
+/// ```
+#[derive(Debug, Default)]
+pub struct ExceptionHierarchy {
+    /// Names of the types to create, and the names of their parents.
+    classes: Vec<(String, Option<String>)>,
+}
+
+impl ExceptionHierarchy {
+    /// Creates an empty hierarchy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a type named `name` which derives from `Exception`.
+    pub fn base(mut self, name: &str) -> Self {
+        self.classes.push((name.to_string(), None));
+        self
+    }
+
+    /// Adds a type named `name` which derives from the type named `parent` of this hierarchy.
+    pub fn subclass(mut self, name: &str, parent: &str) -> Self {
+        self.classes
+            .push((name.to_string(), Some(parent.to_string())));
+        self
+    }
+
+    /// Creates the types, named `<module_name>.<name>`, and returns them by name.
+    ///
+    /// Parents don't need to be added before their subclasses.
+    /// Returns a `ValueError` if a name is added twice, a parent is not part of the hierarchy,
+    /// or the parents form a cycle.
+    pub fn build<'p>(
+        self,
+        py: Python<'p>,
+        module_name: &str,
+    ) -> PyResult<HashMap<String, &'p PyType>> {
+        let mut types = HashMap::with_capacity(self.classes.len());
+        let mut pending = Vec::with_capacity(self.classes.len());
+        for (name, parent) in self.classes {
+            if pending.iter().any(|(pending, _)| *pending == name) {
+                return Err(exceptions::ValueError::py_err(format!(
+                    "exception type '{}' is defined twice",
+                    name
+                )));
+            }
+            pending.push((name, parent));
+        }
+
+        // Create every type whose parent already exists, until no more progress can be made.
+        while !pending.is_empty() {
+            let count = pending.len();
+            let mut i = 0;
+            while i < pending.len() {
+                let base = match pending[i].1 {
+                    None => None,
+                    Some(ref parent) => match types.get(parent) {
+                        Some(base) => Some(*base),
+                        None => {
+                            i += 1;
+                            continue;
+                        }
+                    },
+                };
+                let (name, _) = pending.remove(i);
+                let ty =
+                    PyErr::new_exception(py, &format!("{}.{}", module_name, name), base, None)?;
+                types.insert(name, ty);
+            }
+
+            if pending.len() == count {
+                // A stuck type whose parent isn't pending either can never be created; only
+                // when every stuck parent is itself pending do the parents form a cycle.
+                let unknown = pending.iter().find_map(|(name, parent)| match parent {
+                    Some(parent) if !pending.iter().any(|(pending, _)| pending == parent) => {
+                        Some((name, parent))
+                    }
+                    _ => None,
+                });
+                let message = match unknown {
+                    Some((name, parent)) => {
+                        format!("exception type '{}' has unknown parent '{}'", name, parent)
+                    }
+                    None => format!("exception type '{}' has cyclic parents", pending[0].0),
+                };
+                return Err(exceptions::ValueError::py_err(message));
+            }
+        }
+
+        Ok(types)
+    }
+}
+
+/// Formats `err` followed by each error of its `source()` chain, one per line.
+fn error_chain_message(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str("\ncaused by: ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
 macro_rules! impl_to_pyerr {
     ($err: ty, $pyexc: ty) => {
         impl PyErrArguments for $err {
             fn arguments(&self, py: Python) -> PyObject {
-                self.to_string().to_object(py)
+                error_chain_message(self).to_object(py)
             }
         }
 
@@ -525,6 +1775,23 @@ impl std::convert::From<io::Error> for PyErr {
                 PyErr::from_value::<exceptions::BlockingIOError>(err_value!())
             }
             io::ErrorKind::TimedOut => PyErr::from_value::<exceptions::TimeoutError>(err_value!()),
+            io::ErrorKind::AlreadyExists => {
+                PyErr::from_value::<exceptions::FileExistsError>(err_value!())
+            }
+            io::ErrorKind::PermissionDenied => {
+                PyErr::from_value::<exceptions::PermissionError>(err_value!())
+            }
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => {
+                PyErr::from_value::<exceptions::ValueError>(err_value!())
+            }
+            #[cfg(rustc_1_54)]
+            io::ErrorKind::Unsupported => {
+                PyErr::from_value::<exceptions::NotImplementedError>(err_value!())
+            }
+            #[cfg(rustc_1_54)]
+            io::ErrorKind::OutOfMemory => {
+                PyErr::from_value::<exceptions::MemoryError>(err_value!())
+            }
             _ => PyErr::from_value::<exceptions::OSError>(err_value!()),
         }
     }
@@ -548,7 +1815,19 @@ impl<W: Send + std::fmt::Debug> PyErrArguments for std::io::IntoInnerError<W> {
     }
 }
 
-impl PyErrArguments for std::convert::Infallible {
+impl<T: 'static> std::convert::From<std::sync::mpsc::SendError<T>> for PyErr {
+    fn from(err: std::sync::mpsc::SendError<T>) -> PyErr {
+        PyErr::from_value::<exceptions::RuntimeError>(PyErrValue::from_err_args(err))
+    }
+}
+
+impl<T> PyErrArguments for std::sync::mpsc::SendError<T> {
+    fn arguments(&self, py: Python) -> PyObject {
+        self.to_string().to_object(py)
+    }
+}
+
+impl PyErrArguments for std::convert::Infallible {
     fn arguments(&self, py: Python) -> PyObject {
         "Infalliable!".to_object(py)
     }
@@ -560,7 +1839,53 @@ impl std::convert::From<std::convert::Infallible> for PyErr {
     }
 }
 
+/// Create `OverflowError` from a `ParseIntError` for a number out of range, and `ValueError`
+/// otherwise.
+#[cfg(rustc_1_55)]
+impl std::convert::From<std::num::ParseIntError> for PyErr {
+    fn from(err: std::num::ParseIntError) -> PyErr {
+        use std::num::IntErrorKind;
+        match err.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                PyErr::from_value::<exceptions::OverflowError>(PyErrValue::from_err_args(err))
+            }
+            _ => PyErr::from_value::<exceptions::ValueError>(PyErrValue::from_err_args(err)),
+        }
+    }
+}
+
+#[cfg(rustc_1_55)]
+impl PyErrArguments for std::num::ParseIntError {
+    fn arguments(&self, py: Python) -> PyObject {
+        self.to_string().to_object(py)
+    }
+}
+
+/// Converts a boxed error into a `RuntimeError`.
+///
+/// The message is the error's `Display` output, followed by each error of its `source()` chain.
+impl std::convert::From<Box<dyn std::error::Error + Send + Sync>> for PyErr {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> PyErr {
+        exceptions::RuntimeError::py_err(error_chain_message(&*err))
+    }
+}
+
+/// Tuples are passed as the positional arguments of the exception constructor.
+macro_rules! tuple_err_arguments {
+    ($($T: ident),+) => {
+        impl<$($T: ToPyObject),+> PyErrArguments for ($($T,)+) {
+            fn arguments(&self, py: Python) -> PyObject {
+                self.to_object(py)
+            }
+        }
+    };
+}
+
+tuple_err_arguments!(A, B);
+tuple_err_arguments!(A, B, C);
+
 impl_to_pyerr!(std::array::TryFromSliceError, exceptions::ValueError);
+#[cfg(not(rustc_1_55))]
 impl_to_pyerr!(std::num::ParseIntError, exceptions::ValueError);
 impl_to_pyerr!(std::num::ParseFloatError, exceptions::ValueError);
 impl_to_pyerr!(std::num::TryFromIntError, exceptions::ValueError);
@@ -571,7 +1896,12 @@ impl_to_pyerr!(std::str::Utf8Error, exceptions::UnicodeDecodeError);
 impl_to_pyerr!(std::string::FromUtf8Error, exceptions::UnicodeDecodeError);
 impl_to_pyerr!(std::string::FromUtf16Error, exceptions::UnicodeDecodeError);
 impl_to_pyerr!(std::char::DecodeUtf16Error, exceptions::UnicodeDecodeError);
+impl_to_pyerr!(std::char::CharTryFromError, exceptions::ValueError);
 impl_to_pyerr!(std::net::AddrParseError, exceptions::ValueError);
+impl_to_pyerr!(std::time::SystemTimeError, exceptions::ValueError);
+impl_to_pyerr!(std::sync::mpsc::RecvError, exceptions::RuntimeError);
+impl_to_pyerr!(std::env::VarError, exceptions::OSError);
+impl_to_pyerr!(std::fmt::Error, exceptions::RuntimeError);
 
 pub fn panic_after_error(_py: Python) -> ! {
     unsafe {
@@ -580,6 +1910,19 @@ pub fn panic_after_error(_py: Python) -> ! {
     panic!("Python API call failed");
 }
 
+/// Panics in debug builds if the current thread does not hold the GIL.
+///
+/// The `Python` token should make this impossible, but `Python::assume_gil_acquired` can be
+/// misused; this catches it before calling into the interpreter.
+#[inline]
+fn debug_assert_gil(function: &str) {
+    debug_assert!(
+        gil::gil_is_acquired(),
+        "{} called without holding the GIL",
+        function
+    );
+}
+
 /// Returns Ok if the error code is not -1.
 #[inline]
 pub fn error_on_minusone(py: Python, result: c_int) -> PyResult<()> {
@@ -590,11 +1933,23 @@ pub fn error_on_minusone(py: Python, result: c_int) -> PyResult<()> {
     }
 }
 
+/// Returns Ok if the pointer is not null.
+#[inline]
+pub fn error_on_null<T>(py: Python, ptr: *mut T) -> PyResult<*mut T> {
+    if !ptr.is_null() {
+        Ok(ptr)
+    } else {
+        Err(PyErr::fetch(py))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{ExceptionHierarchy, PyErrArguments, PyErrValue, SendPyErr};
     use crate::exceptions;
     use crate::panic::PanicException;
-    use crate::{PyErr, Python};
+    use crate::types::{IntoPyDict, PyDict, PyModule, PyType};
+    use crate::{ffi, AsPyPointer, AsPyRef, IntoPy, PyErr, PyObject, PyResult, Python, ToPyObject};
 
     #[test]
     fn set_typeerror() {
@@ -625,4 +1980,1486 @@ mod tests {
             std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| PyErr::fetch(py))).is_err();
         assert!(started_unwind);
     }
+
+    /// Returns an exception type whose `__init__` always raises a `RuntimeError`.
+    fn bad_init_type(py: Python) -> &PyType {
+        let module = PyModule::from_code(
+            py,
+            "class BadInit(Exception):\n    def __init__(self, *args):\n        raise RuntimeError('init failed')",
+            "bad_init.py",
+            "bad_init",
+        )
+        .unwrap();
+        module.get("BadInit").unwrap().downcast().unwrap()
+    }
+
+    #[test]
+    fn take_without_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert!(PyErr::take(py).is_none());
+    }
+
+    #[test]
+    fn take_clears_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        exceptions::ValueError::py_err("taken").restore(py);
+        let err = PyErr::take(py).expect("error should be set");
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert!(!PyErr::occurred(py));
+        assert!(PyErr::take(py).is_none());
+    }
+
+    #[test]
+    fn take_panic_exception_panics() {
+        // If -Cpanic=abort is specified, we can't catch panic.
+        if option_env!("RUSTFLAGS")
+            .map(|s| s.contains("-Cpanic=abort"))
+            .unwrap_or(false)
+        {
+            return;
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        PanicException::py_err("new panic").restore(py);
+        let started_unwind =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| PyErr::take(py))).is_err();
+        assert!(started_unwind);
+    }
+
+    #[test]
+    fn new_with_kwargs() {
+        crate::create_exception!(mymodule, CodeError, exceptions::Exception);
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let ctx = [("CodeError", py.get_type::<CodeError>())].into_py_dict(py);
+        py.run(
+            "def init(self, msg, code=None):\n    Exception.__init__(self, msg)\n    self.code = code\nCodeError.__init__ = init",
+            None,
+            Some(ctx),
+        )
+        .unwrap();
+
+        let kwargs = [("code", 42)].into_py_dict(py);
+        let err = PyErr::new_with_kwargs::<CodeError, _>("oops", kwargs);
+        let value = err.value(py);
+        assert_eq!(value.getattr("code").unwrap().extract::<i32>().unwrap(), 42);
+        assert_eq!(
+            err.args(py).unwrap().extract::<(String,)>().unwrap().0,
+            "oops"
+        );
+
+        err.restore(py);
+        let err = PyErr::fetch(py);
+        assert!(err.is_instance::<CodeError>(py));
+        let value = err.value(py);
+        assert_eq!(value.getattr("code").unwrap().extract::<i32>().unwrap(), 42);
+    }
+
+    #[test]
+    fn new_with_kwargs_constructor_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let kwargs = [("bogus", 1)].into_py_dict(py);
+        let mut err = PyErr::new_with_kwargs::<exceptions::ValueError, _>("oops", kwargs);
+        // The constructor rejects the keyword argument, so its error replaces the original one.
+        err.normalize(py);
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+    }
+
+    #[test]
+    fn error_on_null() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let none = py.None();
+        let ptr = none.as_ptr();
+        assert_eq!(super::error_on_null(py, ptr).unwrap(), ptr);
+
+        exceptions::ValueError::py_err("null").restore(py);
+        let err = super::error_on_null(py, std::ptr::null_mut::<ffi::PyObject>()).unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert!(!PyErr::occurred(py));
+    }
+
+    #[test]
+    fn clone_without_gil_token() {
+        let err = exceptions::ValueError::py_err("cloned");
+        let copy = err.clone();
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert!(copy.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(copy.to_string(), "ValueError: cloned");
+        assert_eq!(err.to_string(), "ValueError: cloned");
+    }
+
+    #[test]
+    fn restore_if_unset() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        assert!(exceptions::ValueError::py_err("first").restore_if_unset(py));
+        assert!(!exceptions::TypeError::py_err("second").restore_if_unset(py));
+
+        let err = PyErr::fetch(py);
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(err.to_string(), "ValueError: first");
+    }
+
+    #[test]
+    fn error_chain_in_message() {
+        use super::error_chain_message;
+
+        #[derive(Debug)]
+        struct ConfigError(std::num::ParseIntError);
+
+        impl std::fmt::Display for ConfigError {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("invalid port in config")
+            }
+        }
+
+        impl std::error::Error for ConfigError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        impl_to_pyerr!(ConfigError, exceptions::ValueError);
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err: PyErr = ConfigError("http".parse::<u16>().unwrap_err()).into();
+        assert_eq!(
+            err.to_string(),
+            "ValueError: invalid port in config\ncaused by: invalid digit found in string"
+        );
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    fn traceback() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        assert!(exceptions::ValueError::py_err("lazy")
+            .traceback(py)
+            .is_none());
+
+        let err = py
+            .run("def f():\n    raise ValueError('raised')\nf()", None, None)
+            .unwrap_err();
+        let traceback = err
+            .traceback(py)
+            .expect("raised error should have a traceback");
+        assert_eq!(traceback.get_type().name(), "traceback");
+    }
+
+    #[test]
+    fn exception_hierarchy() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let types = ExceptionHierarchy::new()
+            .subclass("UnexpectedToken", "SyntaxError")
+            .base("ParseError")
+            .subclass("SyntaxError", "ParseError")
+            .build(py, "hierarchy")
+            .unwrap();
+        assert_eq!(types.len(), 3);
+
+        let ctx = [("leaf", types["UnexpectedToken"])].into_py_dict(py);
+        py.run(
+            "assert [t.__name__ for t in leaf.__mro__] == \
+                ['UnexpectedToken', 'SyntaxError', 'ParseError', 'Exception', 'BaseException', 'object']\n\
+             assert leaf.__module__ == 'hierarchy'",
+            None,
+            Some(ctx),
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+    }
+
+    #[test]
+    fn exception_hierarchy_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let unknown = ExceptionHierarchy::new()
+            .base("Base")
+            .subclass("Leaf", "Missing")
+            .build(py, "hierarchy")
+            .unwrap_err();
+        assert_eq!(
+            unknown.to_string(),
+            "ValueError: exception type 'Leaf' has unknown parent 'Missing'"
+        );
+
+        let chained = ExceptionHierarchy::new()
+            .subclass("C", "A")
+            .subclass("A", "Z")
+            .build(py, "hierarchy")
+            .unwrap_err();
+        assert_eq!(
+            chained.to_string(),
+            "ValueError: exception type 'A' has unknown parent 'Z'"
+        );
+
+        let cyclic = ExceptionHierarchy::new()
+            .subclass("A", "B")
+            .subclass("B", "A")
+            .build(py, "hierarchy")
+            .unwrap_err();
+        assert_eq!(
+            cyclic.to_string(),
+            "ValueError: exception type 'A' has cyclic parents"
+        );
+
+        let duplicate = ExceptionHierarchy::new()
+            .base("A")
+            .base("A")
+            .build(py, "hierarchy")
+            .unwrap_err();
+        assert!(duplicate.is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    fn has_value() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let ty = py.get_type::<exceptions::ValueError>();
+        assert!(!PyErr::from_instance(ty).has_value());
+
+        let instance = ty.call1(("instance",)).unwrap();
+        assert!(PyErr::from_instance(instance).has_value());
+
+        assert!(
+            PyErr::from_value::<exceptions::ValueError>(PyErrValue::from_err_args(
+                "args".parse::<i32>().unwrap_err()
+            ))
+            .has_value()
+        );
+
+        assert!(exceptions::ValueError::py_err("object").has_value());
+
+        let kwargs = PyDict::new(py);
+        assert!(PyErr::new_with_kwargs::<exceptions::ValueError, _>("kwargs", kwargs).has_value());
+    }
+
+    #[test]
+    fn with_traceback() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let traceback: PyObject = py
+            .eval(
+                "__import__('types').TracebackType(None, __import__('sys')._getframe(), 0, 42)",
+                None,
+                None,
+            )
+            .unwrap()
+            .into();
+
+        exceptions::ValueError::py_err("synthetic")
+            .with_traceback(Some(traceback.clone_ref(py)))
+            .print_and_set_sys_last_vars(py);
+        let last_traceback = py.import("sys").unwrap().get("last_traceback").unwrap();
+        assert_eq!(last_traceback.as_ptr(), traceback.as_ptr());
+
+        let err = py
+            .run("raise ValueError('raised')", None, None)
+            .unwrap_err();
+        assert!(err.traceback(py).is_some());
+        assert!(err.with_traceback(None).traceback(py).is_none());
+    }
+
+    #[test]
+    fn matched_index() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::ValueError::py_err("value");
+        let exception = py.get_type::<exceptions::Exception>();
+        let value_error = py.get_type::<exceptions::ValueError>();
+        let key_error = py.get_type::<exceptions::KeyError>();
+
+        assert_eq!(err.matched_index(py, &[exception, value_error]), Some(0));
+        assert_eq!(err.matched_index(py, &[key_error, value_error]), Some(1));
+        assert_eq!(err.matched_index(py, &[key_error]), None);
+        assert_eq!(err.matched_index(py, &[]), None);
+    }
+
+    #[test]
+    fn into_normalized_value() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = py
+            .run("raise KeyError('normalized')", None, None)
+            .unwrap_err();
+        let (ptype, instance, traceback) = err.into_normalized_value(py);
+        let ptype = ptype.as_ref(py);
+        assert!(ptype.is_instance(instance.as_ref(py)).unwrap());
+        assert_eq!(
+            ptype.as_ptr(),
+            py.get_type::<exceptions::KeyError>().as_ptr()
+        );
+        assert!(traceback.is_some());
+
+        let (ptype, instance, traceback) =
+            exceptions::ValueError::py_err("lazy").into_normalized_value(py);
+        assert!(ptype.as_ref(py).is_instance(instance.as_ref(py)).unwrap());
+        assert!(traceback.is_none());
+    }
+
+    #[test]
+    fn from_result() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let ptr = PyErr::from_result(py, Ok(42));
+        assert!(!ptr.is_null());
+        assert!(!PyErr::occurred(py));
+        let value: PyObject = unsafe { PyObject::from_owned_ptr(py, ptr) };
+        assert_eq!(value.extract::<i32>(py).unwrap(), 42);
+
+        let result: PyResult<i32> = Err(exceptions::ValueError::py_err("failed"));
+        assert!(PyErr::from_result(py, result).is_null());
+        assert!(PyErr::fetch(py).is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    fn eq_value() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::ValueError::py_err("x");
+        assert!(err.eq_value(py, &exceptions::ValueError::py_err("x")));
+        assert!(err.eq_value(
+            py,
+            &py.run("raise ValueError('x')", None, None).unwrap_err()
+        ));
+        assert!(!err.eq_value(py, &exceptions::ValueError::py_err("y")));
+        assert!(!err.eq_value(py, &exceptions::KeyError::py_err("x")));
+    }
+
+    #[test]
+    fn one_line() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = py.run("raise KeyError('k')", None, None).unwrap_err();
+        assert_eq!(err.one_line(py).unwrap(), "KeyError: 'k'");
+        assert!(err.traceback(py).is_some());
+
+        let err = exceptions::ValueError::py_err("lazy");
+        assert_eq!(err.one_line(py).unwrap(), "ValueError: lazy");
+    }
+
+    #[test]
+    fn send_pyerr_across_threads() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        py.allow_threads(move || {
+            std::thread::spawn(move || {
+                let gil = Python::acquire_gil();
+                let py = gil.python();
+                let err = py
+                    .run(
+                        "def work():\n    raise ValueError('bad input')\nwork()",
+                        None,
+                        None,
+                    )
+                    .unwrap_err();
+                sender.send(SendPyErr::new(py, &err)).unwrap();
+            })
+            .join()
+            .unwrap();
+        });
+
+        let captured = receiver.recv().unwrap();
+        assert_eq!(captured.type_name(), "ValueError");
+        assert_eq!(captured.message(), "bad input");
+        assert!(captured.traceback().unwrap().contains("in work"));
+
+        let err = captured.into_pyerr(py);
+        assert!(err.is_instance::<exceptions::RuntimeError>(py));
+        assert_eq!(err.to_string(), "RuntimeError: ValueError: bad input");
+    }
+
+    #[test]
+    fn tuple_arguments() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = PyErr::from_value::<exceptions::OSError>(PyErrValue::from_err_args((2, "nope")));
+        let value = err.value(py);
+        assert!(err.is_instance::<exceptions::OSError>(py));
+        assert_eq!(value.getattr("errno").unwrap().extract::<i32>().unwrap(), 2);
+        assert_eq!(
+            value
+                .getattr("strerror")
+                .unwrap()
+                .extract::<&str>()
+                .unwrap(),
+            "nope"
+        );
+        assert_eq!(
+            err.args(py).unwrap().extract::<(i32, &str)>().unwrap(),
+            (2, "nope")
+        );
+
+        let err = PyErr::from_value::<exceptions::OSError>(PyErrValue::from_err_args((
+            2,
+            "nope",
+            "missing.txt",
+        )));
+        assert_eq!(
+            err.value(py)
+                .getattr("filename")
+                .unwrap()
+                .extract::<&str>()
+                .unwrap(),
+            "missing.txt"
+        );
+    }
+
+    #[test]
+    fn set_context() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let mut err = exceptions::ValueError::py_err("outer");
+        err.set_context(py, Some(exceptions::KeyError::py_err("inner")));
+        err.print_and_set_sys_last_vars(py);
+
+        let ctx = [("sys", py.import("sys").unwrap())].into_py_dict(py);
+        py.run(
+            "last = sys.last_value\n\
+             assert isinstance(last.__context__, KeyError)\n\
+             assert last.__cause__ is None\n\
+             assert not last.__suppress_context__",
+            None,
+            Some(ctx),
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+
+        let mut err = exceptions::ValueError::py_err("outer");
+        err.set_context(py, Some(exceptions::KeyError::py_err("inner")));
+        err.set_context(py, None);
+        assert!(err.value(py).getattr("__context__").unwrap().is_none());
+    }
+
+    #[test]
+    fn fetch_raw_does_not_resume_panic() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        PanicException::py_err("new panic").restore(py);
+        let err = PyErr::fetch_raw(py);
+        assert!(err.is_instance::<PanicException>(py));
+        assert!(!PyErr::occurred(py));
+
+        assert!(PyErr::fetch_raw(py).is_instance::<exceptions::SystemError>(py));
+    }
+
+    #[test]
+    fn to_exit_code() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let exit_code = |code| py.run(code, None, None).unwrap_err().to_exit_code(py);
+        assert_eq!(exit_code("raise SystemExit(3)"), 3);
+        assert_eq!(exit_code("raise SystemExit('msg')"), 1);
+        assert_eq!(exit_code("raise SystemExit()"), 0);
+        assert_eq!(exit_code("raise SystemExit(None)"), 0);
+        assert_eq!(exit_code("raise ValueError(3)"), 1);
+        assert_eq!(exceptions::SystemExit::py_err(4).to_exit_code(py), 4);
+    }
+
+    #[test]
+    fn instance_ref() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let mut err = exceptions::ValueError::py_err("borrowed");
+        assert!(err.instance_ref(py).is_none());
+
+        err.normalize(py);
+        for _ in 0..2 {
+            let instance = err
+                .instance_ref(py)
+                .expect("normalized error has an instance");
+            let args: (&str,) = instance.getattr("args").unwrap().extract().unwrap();
+            assert_eq!(args, ("borrowed",));
+        }
+    }
+
+    #[test]
+    fn try_arguments_error_is_raised() {
+        struct ImportedArgs;
+
+        impl PyErrArguments for ImportedArgs {
+            fn arguments(&self, py: Python) -> PyObject {
+                "fallback".to_object(py)
+            }
+
+            fn try_arguments(&self, py: Python) -> PyResult<PyObject> {
+                Ok(py.import("no_such_module_for_args")?.to_object(py))
+            }
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let mut err =
+            PyErr::from_value::<exceptions::ValueError>(PyErrValue::ToArgs(Box::new(ImportedArgs)));
+        err.normalize(py);
+        assert!(err.is_instance::<exceptions::ImportError>(py));
+
+        let err =
+            PyErr::from_value::<exceptions::ValueError>(PyErrValue::from_err_args(ImportedArgs));
+        err.clone_ref(py).restore(py);
+        assert!(PyErr::fetch(py).is_instance::<exceptions::ImportError>(py));
+        err.restore(py);
+        assert!(PyErr::fetch(py).is_instance::<exceptions::ImportError>(py));
+    }
+
+    #[test]
+    fn pending_args() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let ty = py.get_type::<exceptions::ValueError>();
+        assert!(PyErr::from_instance(ty).pending_args(py).is_none(py));
+
+        let instance = ty.call1(("instance",)).unwrap();
+        let args = PyErr::from_instance(instance).pending_args(py);
+        assert_eq!(args.as_ptr(), instance.as_ptr());
+
+        let err = PyErr::from_value::<exceptions::ValueError>(PyErrValue::from_err_args((1, 2)));
+        assert_eq!(
+            err.pending_args(py).extract::<(i32, i32)>(py).unwrap(),
+            (1, 2)
+        );
+
+        let err = exceptions::ValueError::py_err("object");
+        assert_eq!(err.pending_args(py).extract::<&str>(py).unwrap(), "object");
+
+        let kwargs = [("code", 1)].into_py_dict(py);
+        let err = PyErr::new_with_kwargs::<exceptions::ValueError, _>("kwargs", kwargs);
+        assert_eq!(
+            err.pending_args(py).extract::<(&str,)>(py).unwrap(),
+            ("kwargs",)
+        );
+    }
+
+    #[test]
+    fn err_shorthand() {
+        fn parse(value: &str) -> PyResult<i32> {
+            if value.is_empty() {
+                return exceptions::ValueError::py_err("empty").err();
+            }
+            Ok(value.len() as i32)
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert_eq!(parse("abc").unwrap(), 3);
+        assert!(parse("")
+            .unwrap_err()
+            .is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    fn add_note() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut err = exceptions::ValueError::py_err("with notes");
+
+        if py.version_info().unwrap() < (3, 11, 0) {
+            let note_err = err.add_note(py, "first").unwrap_err();
+            assert!(note_err.is_instance::<exceptions::NotImplementedError>(py));
+            return;
+        }
+
+        err.add_note(py, "first").unwrap();
+        err.add_note(py, "second").unwrap();
+        let notes: Vec<String> = err
+            .instance(py)
+            .getattr(py, "__notes__")
+            .unwrap()
+            .extract(py)
+            .unwrap();
+        assert_eq!(notes, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn with_saved() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        exceptions::ValueError::py_err("original").restore(py);
+        let result = PyErr::with_saved(py, |py| {
+            assert!(!PyErr::occurred(py));
+            exceptions::TypeError::py_err("cleanup").restore(py);
+            let err = PyErr::fetch(py);
+            assert!(err.is_instance::<exceptions::TypeError>(py));
+            42
+        });
+        assert_eq!(result, 42);
+
+        let err = PyErr::fetch(py);
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(
+            err.instance(py).as_ref(py).str().unwrap().to_string_lossy(),
+            "original"
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "PyErr::occurred called without holding the GIL")]
+    fn occurred_without_gil() {
+        // This test thread has never acquired the GIL.
+        let py = unsafe { Python::assume_gil_acquired() };
+        PyErr::occurred(py);
+    }
+
+    #[test]
+    fn into_py_preserves_traceback() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let traceback = py
+            .run("raise ValueError('with traceback')", None, None)
+            .unwrap_err()
+            .traceback(py)
+            .expect("raised error should have a traceback")
+            .to_object(py);
+
+        let err =
+            exceptions::KeyError::py_err("lazy").with_traceback(Some(traceback.clone_ref(py)));
+        let obj: PyObject = (&err).into_py(py);
+        let obj_traceback = obj.getattr(py, "__traceback__").unwrap();
+        assert_eq!(obj_traceback.as_ptr(), traceback.as_ptr());
+
+        let obj: PyObject = err.into_py(py);
+        let obj_traceback = obj.getattr(py, "__traceback__").unwrap();
+        assert_eq!(obj_traceback.as_ptr(), traceback.as_ptr());
+    }
+
+    #[test]
+    fn unicode_decode_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let bytes = b"abc\xff\xfedef";
+        let utf8_err = std::str::from_utf8(bytes).unwrap_err();
+        let err = PyErr::unicode_decode_error(py, bytes, utf8_err).unwrap();
+        assert!(err.is_instance::<exceptions::UnicodeDecodeError>(py));
+
+        let value = err.value(py);
+        assert_eq!(
+            value.getattr("start").unwrap().extract::<usize>().unwrap(),
+            3
+        );
+        assert_eq!(value.getattr("end").unwrap().extract::<usize>().unwrap(), 4);
+        assert_eq!(
+            value.getattr("reason").unwrap().extract::<&str>().unwrap(),
+            "invalid utf-8"
+        );
+
+        // A truncated character spans to the end of the input.
+        let bytes = b"abc\xe2\x82";
+        let utf8_err = std::str::from_utf8(bytes).unwrap_err();
+        let value = PyErr::unicode_decode_error(py, bytes, utf8_err)
+            .unwrap()
+            .value(py);
+        assert_eq!(
+            value.getattr("start").unwrap().extract::<usize>().unwrap(),
+            3
+        );
+        assert_eq!(value.getattr("end").unwrap().extract::<usize>().unwrap(), 5);
+    }
+
+    #[test]
+    fn clear() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        exceptions::ValueError::py_err("ignored").restore(py);
+        assert!(PyErr::occurred(py));
+        PyErr::clear(py);
+        assert!(!PyErr::occurred(py));
+
+        // Clearing without an error set is fine.
+        PyErr::clear(py);
+        assert!(!PyErr::occurred(py));
+
+        exceptions::ValueError::py_err("ignored").ignore();
+        assert!(!PyErr::occurred(py));
+    }
+
+    #[test]
+    fn from_boxed_error() {
+        #[derive(Debug)]
+        struct Inner;
+
+        impl std::fmt::Display for Inner {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("inner failure")
+            }
+        }
+
+        impl std::error::Error for Inner {}
+
+        #[derive(Debug)]
+        struct Outer(Inner);
+
+        impl std::fmt::Display for Outer {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("outer failure")
+            }
+        }
+
+        impl std::error::Error for Outer {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        fn fallible() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Err(Box::new(Outer(Inner)))
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err: PyErr = fallible().unwrap_err().into();
+        assert!(err.is_instance::<exceptions::RuntimeError>(py));
+        assert_eq!(
+            err.value(py).str().unwrap().to_string_lossy(),
+            "outer failure\ncaused by: inner failure"
+        );
+    }
+
+    #[test]
+    fn type_name() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::KeyError::py_err("key");
+        assert_eq!(err.type_name(py).unwrap(), "KeyError");
+
+        crate::create_exception!(mymodule, CustomError, exceptions::Exception);
+        let err = CustomError::py_err("custom");
+        assert_eq!(err.type_name(py).unwrap(), "CustomError");
+    }
+
+    #[test]
+    fn into_parts() {
+        use crate::IntoPyPointer;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = py.run("raise KeyError('parts')", None, None).unwrap_err();
+        let (ptype, instance, traceback) = err.into_parts(py);
+        assert_eq!(
+            ptype.as_ptr(),
+            py.get_type::<exceptions::KeyError>().as_ptr()
+        );
+        let traceback = traceback.expect("raised error should have a traceback");
+        let instance_ptr = instance.as_ptr();
+
+        unsafe {
+            ffi::PyErr_Restore(
+                ptype.into_ptr(),
+                instance.into_ptr(),
+                traceback.clone_ref(py).into_ptr(),
+            )
+        };
+        let err = PyErr::fetch(py);
+        assert!(err.is_instance::<exceptions::KeyError>(py));
+        assert_eq!(err.instance_ref(py).unwrap().as_ptr(), instance_ptr);
+        assert_eq!(err.traceback(py).unwrap().as_ptr(), traceback.as_ptr());
+    }
+
+    #[test]
+    #[cfg(rustc_1_65)]
+    fn rust_backtrace() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let mut err = exceptions::ValueError::py_err("backtrace").with_rust_backtrace();
+        assert!(err.rust_backtrace().is_some());
+        assert!(err.clone_ref(py).rust_backtrace().is_some());
+        err.normalize(py);
+        assert!(err.rust_backtrace().is_some());
+
+        let err = py.run("raise ValueError()", None, None).unwrap_err();
+        assert!(err.rust_backtrace().is_none());
+    }
+
+    #[test]
+    fn map_pyerr_type() {
+        use super::PyResultExt;
+
+        crate::create_exception!(mymodule, LookupFailed, exceptions::Exception);
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let is_key_error = |err: &PyErr| err.is_instance::<exceptions::KeyError>(py);
+
+        let result: PyResult<i32> = Err(exceptions::KeyError::py_err("missing"));
+        let err = result
+            .map_pyerr_type::<LookupFailed, _>(py, is_key_error)
+            .unwrap_err();
+        assert!(err.is_instance::<LookupFailed>(py));
+        assert_eq!(err.value(py).str().unwrap().to_string_lossy(), "'missing'");
+        let cause = err.value(py).getattr("__cause__").unwrap();
+        assert!(cause
+            .get_type()
+            .is_subclass::<exceptions::KeyError>()
+            .unwrap());
+
+        let result: PyResult<i32> = Err(exceptions::TypeError::py_err("other"));
+        let err = result
+            .map_pyerr_type::<LookupFailed, _>(py, is_key_error)
+            .unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+
+        let result: PyResult<i32> = Ok(1);
+        assert_eq!(
+            result
+                .map_pyerr_type::<LookupFailed, _>(py, is_key_error)
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn parse_int_error() {
+        use std::convert::TryFrom;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err: PyErr = "abc".parse::<i32>().unwrap_err().into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert!(!err.is_instance::<exceptions::OverflowError>(py));
+
+        #[cfg(rustc_1_55)]
+        {
+            let err: PyErr = "300".parse::<u8>().unwrap_err().into();
+            assert!(err.is_instance::<exceptions::OverflowError>(py));
+            let err: PyErr = "-300".parse::<i8>().unwrap_err().into();
+            assert!(err.is_instance::<exceptions::OverflowError>(py));
+            assert_eq!(
+                err.value(py).str().unwrap().to_string_lossy(),
+                "number too small to fit in target type"
+            );
+        }
+
+        let err: PyErr = char::try_from(0xD800u32).unwrap_err().into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    fn capture_message() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::ValueError::py_err("bad input");
+        assert_eq!(err.capture_message(py), "ValueError: bad input");
+
+        let err = exceptions::KeyError::py_err("key");
+        assert_eq!(err.capture_message(py), "KeyError: 'key'");
+
+        let err = exceptions::StopIteration::py_err(());
+        assert_eq!(err.capture_message(py), "StopIteration");
+
+        let err = py.run("1 / 0", None, None).unwrap_err();
+        assert_eq!(
+            err.capture_message(py),
+            "ZeroDivisionError: division by zero"
+        );
+
+        let err = PyErr::from_type(bad_init_type(py), "lost");
+        assert_eq!(err.capture_message(py), "BadInit");
+        assert!(!PyErr::occurred(py));
+    }
+
+    #[test]
+    fn warn_category() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let warnings = py.import("warnings").unwrap();
+        let catch_warnings = warnings.call0("catch_warnings").unwrap();
+        catch_warnings.call_method0("__enter__").unwrap();
+        warnings
+            .call1(
+                "simplefilter",
+                ("error", py.get_type::<exceptions::DeprecationWarning>()),
+            )
+            .unwrap();
+
+        let result =
+            PyErr::warn_category::<exceptions::DeprecationWarning>(py, "old", 1).map_err(|err| {
+                (
+                    err.is_instance::<exceptions::DeprecationWarning>(py),
+                    err.capture_message(py),
+                )
+            });
+
+        catch_warnings
+            .call_method1("__exit__", (py.None(), py.None(), py.None()))
+            .unwrap();
+        assert_eq!(result, Err((true, "DeprecationWarning: old".to_string())));
+    }
+
+    #[test]
+    fn os_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = PyErr::os_error(py, libc::ENOENT, "No such file", Some("missing.txt"));
+        assert!(err.is_instance::<exceptions::OSError>(py));
+        assert_eq!(err.errno(py), Some(libc::ENOENT));
+        assert_eq!(err.strerror(py), Some("No such file".to_string()));
+        assert_eq!(err.filename(py), Some("missing.txt".to_string()));
+        assert!(err
+            .value(py)
+            .get_type()
+            .is_subclass::<exceptions::FileNotFoundError>()
+            .unwrap());
+
+        let err = PyErr::os_error(py, libc::EPERM, "Not permitted", None);
+        assert_eq!(err.errno(py), Some(libc::EPERM));
+        assert_eq!(err.filename(py), None);
+
+        let err = py.run("open('/nonexistent/pyo3')", None, None).unwrap_err();
+        assert_eq!(err.errno(py), Some(libc::ENOENT));
+        assert_eq!(err.filename(py), Some("/nonexistent/pyo3".to_string()));
+
+        let err = exceptions::ValueError::py_err("not an OSError");
+        assert_eq!(err.errno(py), None);
+        assert_eq!(err.strerror(py), None);
+        assert!(!PyErr::occurred(py));
+    }
+
+    #[test]
+    fn restore_checked() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        assert!(PanicException::py_err("panicked").restore_checked(py));
+        let err = PyErr::fetch_raw(py);
+        assert!(err.is_instance::<PanicException>(py));
+
+        assert!(!exceptions::ValueError::py_err("not a panic").restore_checked(py));
+        let err = PyErr::fetch(py);
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    fn is_instance_of() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let base = py.get_type::<exceptions::ValueError>();
+        let ty = PyErr::new_exception(py, "mymod.RuntimeDefined", Some(base), None).unwrap();
+        let other = PyErr::new_exception(py, "mymod.Other", None, None).unwrap();
+
+        let err = PyErr::from_type(ty, "raised");
+        assert!(err.is_instance_of(py, ty));
+        assert!(err.is_instance_of(py, base));
+        assert!(!err.is_instance_of(py, other));
+
+        let err = exceptions::ValueError::py_err("base");
+        assert!(!err.is_instance_of(py, ty));
+    }
+
+    #[test]
+    fn from_type_value() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let builtins = py.import("builtins").unwrap();
+        let ty: &PyType = builtins.get("ValueError").unwrap().downcast().unwrap();
+
+        let err = PyErr::from_type_value(ty, PyErrValue::ToObject(Box::new("runtime")));
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(err.to_string(), "ValueError: runtime");
+    }
+
+    #[test]
+    fn warn_stacklevel() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let category = py.get_type::<exceptions::UserWarning>();
+        let err = PyErr::warn(py, category, "never issued", 0).unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+
+        let err = PyErr::warn(py, category, "never issued", usize::MAX).unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert!(!PyErr::occurred(py));
+    }
+
+    #[test]
+    fn context_chain() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = py
+            .run(
+                "try:\n    raise KeyError('first')\nexcept KeyError:\n    try:\n        raise TypeError('second')\n    except TypeError:\n        raise ValueError('third')",
+                None,
+                None,
+            )
+            .unwrap_err();
+        let chain = err.context_chain(py).unwrap();
+        assert_eq!(chain.len(), 2);
+        assert!(chain[0].is_instance::<exceptions::TypeError>(py));
+        assert!(chain[1].is_instance::<exceptions::KeyError>(py));
+
+        assert!(exceptions::ValueError::py_err("no context")
+            .context_chain(py)
+            .unwrap()
+            .is_empty());
+
+        // A cycle is only followed once.
+        let mut first = exceptions::ValueError::py_err("first");
+        first.normalize(py);
+        let mut second = exceptions::TypeError::py_err("second");
+        second.set_context(py, Some(first.clone_ref(py)));
+        first.set_context(py, Some(second.clone_ref(py)));
+        let chain = first.context_chain(py).unwrap();
+        assert_eq!(chain.len(), 1);
+        assert!(chain[0].is_instance::<exceptions::TypeError>(py));
+    }
+
+    #[test]
+    fn display_fetched_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err = py
+            .run("int('abc')", None, None)
+            .expect_err("int('abc') should raise");
+        assert_eq!(
+            err.to_string(),
+            "ValueError: invalid literal for int() with base 10: 'abc'"
+        );
+
+        let err: PyErr = exceptions::KeyError.into();
+        assert_eq!(err.to_string(), "KeyError");
+
+        // An exception that fails to normalize is shown by its type name alone, rather than
+        // with the message of the error raised while normalizing.
+        assert_eq!(
+            PyErr::from_type(bad_init_type(py), "lost").to_string(),
+            "BadInit"
+        );
+    }
+
+    #[test]
+    fn error_source_follows_chain() {
+        use std::error::Error;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err = py
+            .run(
+                "try:\n    raise KeyError('inner')\nexcept KeyError as e:\n    raise ValueError('outer') from e",
+                None,
+                None,
+            )
+            .expect_err("code should raise");
+        assert_eq!(err.to_string(), "ValueError: outer");
+        let source = err.source().expect("error should have a cause");
+        assert_eq!(source.to_string(), "KeyError: 'inner'");
+        assert!(source.source().is_none());
+
+        let boxed: Box<dyn Error> = Box::new(err);
+        assert_eq!(boxed.to_string(), "ValueError: outer");
+    }
+
+    #[test]
+    fn clone_ref_shares_lazy_arguments() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct CountingArgs(Rc<Cell<usize>>);
+
+        impl PyErrArguments for CountingArgs {
+            fn arguments(&self, py: Python) -> PyObject {
+                self.0.set(self.0.get() + 1);
+                "counted".to_object(py)
+            }
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let calls = Rc::new(Cell::new(0));
+        let err = PyErr::from_value::<exceptions::ValueError>(PyErrValue::from_err_args(
+            CountingArgs(calls.clone()),
+        ));
+        let clones: Vec<PyErr> = (0..3).map(|_| err.clone_ref(py)).collect();
+        assert_eq!(calls.get(), 0);
+
+        for clone in clones {
+            clone.restore(py);
+            let fetched = PyErr::fetch(py);
+            assert_eq!(fetched.to_string(), "ValueError: counted");
+        }
+        err.restore(py);
+        PyErr::fetch(py);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn into_anyhow() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = py
+            .run("int('abc')", None, None)
+            .expect_err("int('abc') should raise");
+        let err = err.into_anyhow();
+        assert_eq!(
+            err.to_string(),
+            "ValueError: invalid literal for int() with base 10: 'abc'"
+        );
+        assert!(err.root_cause().to_string().starts_with("Traceback"));
+
+        let err = exceptions::KeyError::py_err("key").into_anyhow();
+        assert_eq!(err.to_string(), "KeyError: 'key'");
+        assert_eq!(err.chain().count(), 1);
+    }
+
+    #[test]
+    fn std_error_conversions() {
+        use std::sync::mpsc;
+        use std::time::{Duration, SystemTime};
+
+        fn system_time() -> PyResult<Duration> {
+            Ok(SystemTime::UNIX_EPOCH.duration_since(SystemTime::now())?)
+        }
+
+        fn recv() -> PyResult<()> {
+            let (_, rx) = mpsc::channel::<()>();
+            Ok(rx.recv()?)
+        }
+
+        fn send() -> PyResult<()> {
+            let (tx, _) = mpsc::channel();
+            Ok(tx.send(1)?)
+        }
+
+        fn var() -> PyResult<String> {
+            Ok(std::env::var("PYO3_TEST_VAR_THAT_IS_NOT_SET")?)
+        }
+
+        fn fmt() -> PyResult<()> {
+            Err(std::fmt::Error)?
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert!(system_time()
+            .unwrap_err()
+            .is_instance::<exceptions::ValueError>(py));
+        assert!(recv()
+            .unwrap_err()
+            .is_instance::<exceptions::RuntimeError>(py));
+        assert!(send()
+            .unwrap_err()
+            .is_instance::<exceptions::RuntimeError>(py));
+        assert!(var().unwrap_err().is_instance::<exceptions::OSError>(py));
+        assert!(fmt()
+            .unwrap_err()
+            .is_instance::<exceptions::RuntimeError>(py));
+    }
+
+    #[test]
+    fn is_exact_type() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = py
+            .run("{}['missing']", None, None)
+            .expect_err("lookup should raise");
+        let lookup_error = py.get_type::<exceptions::LookupError>();
+        assert!(err.matches(py, lookup_error));
+        assert!(!err.is_exact_type(py, lookup_error));
+        assert!(err.is_exact_type(py, py.get_type::<exceptions::KeyError>()));
+    }
+
+    #[test]
+    fn write_unraisable() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::RuntimeError::py_err("cannot propagate");
+        err.write_unraisable(py, Some(py.None().as_ref(py)));
+        assert!(!PyErr::occurred(py));
+
+        exceptions::RuntimeError::py_err("no context").write_unraisable(py, None);
+        assert!(!PyErr::occurred(py));
+    }
+
+    #[test]
+    fn downcast_error_message() {
+        use crate::types::PyList;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let dict = crate::types::PyDict::new(py);
+        let err: PyErr = dict.downcast::<PyList>().unwrap_err().into();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+        assert_eq!(err.to_string(), "TypeError: expected PyList, got dict");
+    }
+
+    #[test]
+    fn matches_any() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = py
+            .run("{}['missing']", None, None)
+            .expect_err("lookup should raise");
+        let types = vec![
+            py.get_type::<exceptions::ValueError>(),
+            py.get_type::<exceptions::KeyError>(),
+        ];
+        assert!(err.matches_any(py, types));
+        assert!(err.matches_any(py, vec![py.get_type::<exceptions::LookupError>()]));
+        assert!(!err.matches_any(py, vec![py.get_type::<exceptions::ValueError>()]));
+        assert!(!err.matches_any(py, Vec::new()));
+    }
+
+    #[test]
+    fn new_exception() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let base = py.get_type::<exceptions::ValueError>();
+        let ty = PyErr::new_exception(py, "mymod.MyError", Some(base), None).unwrap();
+        assert_eq!(ty.name(), "MyError");
+
+        let locals = [("MyError", ty)].into_py_dict(py);
+        let err = py
+            .run("raise MyError('raised')", None, Some(locals))
+            .expect_err("MyError should be raised");
+        assert!(err.matches(py, ty));
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        py.run(
+            "assert MyError.__module__ == 'mymod'; assert MyError.__mro__[1:3] == (ValueError, Exception)",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let err = PyErr::new_exception(py, "NoModule", None, None).unwrap_err();
+        assert!(err.is_instance::<exceptions::SystemError>(py));
+    }
+
+    #[test]
+    fn io_error_kinds() {
+        use std::io;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err: PyErr = io::Error::from(io::ErrorKind::PermissionDenied).into();
+        assert!(err.is_instance::<exceptions::PermissionError>(py));
+        let err: PyErr = io::Error::from(io::ErrorKind::AlreadyExists).into();
+        assert!(err.is_instance::<exceptions::FileExistsError>(py));
+        let err: PyErr = io::Error::new(io::ErrorKind::InvalidData, "bad data").into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(err.to_string(), "ValueError: bad data");
+        #[cfg(rustc_1_54)]
+        {
+            let err: PyErr = io::Error::from(io::ErrorKind::Unsupported).into();
+            assert!(err.is_instance::<exceptions::NotImplementedError>(py));
+        }
+        let err: PyErr = io::Error::from(io::ErrorKind::Other).into();
+        assert!(err.is_instance::<exceptions::OSError>(py));
+    }
+
+    #[test]
+    fn debug_includes_value() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::TypeError::py_err("boom");
+        assert!(format!("{:?}", err).contains("boom"));
+
+        let mut err = exceptions::TypeError::py_err("boom");
+        err.normalize(py);
+        assert!(format!("{:?}", err).contains("TypeError('boom')"));
+
+        let err: PyErr = "abc".parse::<i32>().unwrap_err().into();
+        assert!(format!("{:?}", err).contains("<lazy args>"));
+    }
+
+    #[test]
+    fn from_instance_with_traceback() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let module = PyModule::from_code(
+            py,
+            "def caught():\n    try:\n        raise ValueError('origin')\n    except ValueError as e:\n        return e",
+            "caught.py",
+            "caught",
+        )
+        .unwrap();
+        let instance = module.call0("caught").unwrap();
+        let traceback = instance.getattr("__traceback__").unwrap();
+
+        let err = PyErr::from_instance_with_traceback(instance, Some(traceback));
+        assert!(err.ptraceback.is_some());
+        assert!(err.format_traceback(py).unwrap().contains("caught.py"));
+        assert!(PyErr::from_instance_with_traceback(instance, None)
+            .ptraceback
+            .is_none());
+    }
+
+    #[test]
+    fn value_returns_instance() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::ValueError::py_err("msg");
+        let value = err.value(py);
+        assert!(value
+            .get_type()
+            .is_subclass::<exceptions::ValueError>()
+            .unwrap());
+        let arg: &str = value
+            .getattr("args")
+            .unwrap()
+            .get_item(0)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(arg, "msg");
+        // The error can still be used afterwards.
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    fn args() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = exceptions::OSError::py_err((2, "No such file"));
+        let args = err.args(py).unwrap();
+        assert_eq!(args.len(), 2);
+        assert_eq!(args.get_item(0).extract::<i32>().unwrap(), 2);
+        assert_eq!(args.get_item(1).extract::<&str>().unwrap(), "No such file");
+
+        let err: PyErr = exceptions::KeyError.into();
+        assert!(err.args(py).unwrap().is_empty());
+    }
+
+    #[test]
+    fn format_traceback() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = PyModule::from_code(
+            py,
+            "def fail():\n    raise ValueError('oops')\nfail()",
+            "failing.py",
+            "failing",
+        )
+        .expect_err("module code should raise");
+        let formatted = err.format_traceback(py).unwrap();
+        assert!(formatted.starts_with("Traceback (most recent call last):\n"));
+        assert!(formatted.contains("failing.py"));
+        assert!(formatted.ends_with("ValueError: oops\n"));
+        // `self` is left untouched.
+        assert_eq!(err.format_traceback(py).unwrap(), formatted);
+
+        let err = exceptions::KeyError::py_err("missing");
+        assert_eq!(err.format_traceback(py).unwrap(), "KeyError: 'missing'\n");
+    }
+
+    #[test]
+    fn set_cause_round_trip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let mut err = exceptions::ValueError::py_err("outer");
+        assert!(err.cause(py).is_none());
+
+        let cause = exceptions::KeyError::py_err("inner");
+        let cause_instance = cause.to_object(py);
+        err.set_cause(py, Some(PyErr::from_instance(cause_instance.as_ref(py))))
+            .unwrap();
+        assert!(err
+            .cause(py)
+            .unwrap()
+            .is_instance::<exceptions::KeyError>(py));
+
+        err.print_and_set_sys_last_vars(py);
+        let locals = [("cause", cause_instance)].into_py_dict(py);
+        py.run(
+            "import sys; assert sys.last_value.__cause__ is cause; assert sys.last_value.__suppress_context__",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let mut err = exceptions::ValueError::py_err("outer");
+        err.set_cause(py, Some(exceptions::KeyError::py_err("inner")))
+            .unwrap();
+        err.set_cause(py, None).unwrap();
+        assert!(err.cause(py).is_none());
+        let locals = [("err", err)].into_py_dict(py);
+        py.run("assert not err.__suppress_context__", None, Some(locals))
+            .unwrap();
+    }
+
+    #[test]
+    fn clone_ref_failing_arguments() {
+        struct FailingArgs;
+
+        impl PyErrArguments for FailingArgs {
+            fn arguments(&self, py: Python) -> PyObject {
+                "fallback".to_object(py)
+            }
+
+            fn try_arguments(&self, py: Python) -> PyResult<PyObject> {
+                Ok(py.import("no_such_module_for_clone")?.to_object(py))
+            }
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err =
+            PyErr::from_value::<exceptions::ValueError>(PyErrValue::ToArgs(Box::new(FailingArgs)));
+        let mut copy = err.clone_ref(py);
+        copy.normalize(py);
+        assert!(copy.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(copy.to_string(), "ValueError: fallback");
+    }
 }
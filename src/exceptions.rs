@@ -289,6 +289,18 @@ impl_native_exception!(IOError, PyExc_IOError);
 #[cfg(target_os = "windows")]
 impl_native_exception!(WindowsError, PyExc_WindowsError);
 
+impl_native_exception!(Warning, PyExc_Warning);
+impl_native_exception!(UserWarning, PyExc_UserWarning);
+impl_native_exception!(DeprecationWarning, PyExc_DeprecationWarning);
+impl_native_exception!(PendingDeprecationWarning, PyExc_PendingDeprecationWarning);
+impl_native_exception!(SyntaxWarning, PyExc_SyntaxWarning);
+impl_native_exception!(RuntimeWarning, PyExc_RuntimeWarning);
+impl_native_exception!(FutureWarning, PyExc_FutureWarning);
+impl_native_exception!(ImportWarning, PyExc_ImportWarning);
+impl_native_exception!(UnicodeWarning, PyExc_UnicodeWarning);
+impl_native_exception!(BytesWarning, PyExc_BytesWarning);
+impl_native_exception!(ResourceWarning, PyExc_ResourceWarning);
+
 impl UnicodeDecodeError {
     pub fn new_err<'p>(
         py: Python<'p>,
@@ -317,11 +329,13 @@ impl UnicodeDecodeError {
         err: std::str::Utf8Error,
     ) -> PyResult<&'p PyAny> {
         let pos = err.valid_up_to();
+        // `error_len` is `None` if the input ends in the middle of a character.
+        let end = err.error_len().map_or(input.len(), |len| pos + len);
         UnicodeDecodeError::new_err(
             py,
             CStr::from_bytes_with_nul(b"utf-8\0").unwrap(),
             input,
-            pos..pos + 1,
+            pos..end,
             CStr::from_bytes_with_nul(b"invalid utf-8\0").unwrap(),
         )
     }
@@ -138,7 +138,10 @@ pub use crate::conversion::{
     AsPyPointer, FromPy, FromPyObject, FromPyPointer, IntoPy, IntoPyPointer, PyTryFrom, PyTryInto,
     ToBorrowedObject, ToPyObject,
 };
-pub use crate::err::{PyDowncastError, PyErr, PyErrArguments, PyErrValue, PyResult};
+pub use crate::err::{
+    ExceptionHierarchy, PyDowncastError, PyErr, PyErrArguments, PyErrException, PyErrValue,
+    PyResult, PyResultExt, SendPyErr,
+};
 pub use crate::gil::{GILGuard, GILPool};
 pub use crate::instance::{AsPyRef, Py, PyNativeType};
 pub use crate::object::PyObject;
@@ -259,6 +259,18 @@ impl<'p> Python<'p> {
         PyModule::import(self, name)
     }
 
+    /// Returns the `(major, minor, micro)` version of the running interpreter.
+    ///
+    /// This reads `sys.version_info` each time it is called.
+    pub fn version_info(self) -> PyResult<(u8, u8, u8)> {
+        let version_info = self.import("sys")?.get("version_info")?;
+        Ok((
+            version_info.getattr("major")?.extract()?,
+            version_info.getattr("minor")?.extract()?,
+            version_info.getattr("micro")?.extract()?,
+        ))
+    }
+
     /// Checks whether `obj` is an instance of type `T`.
     ///
     /// This is equivalent to the Python `isinstance` function.
@@ -529,6 +541,20 @@ mod test {
         assert!(!py.is_subclass::<PyBool, PyList>().unwrap());
     }
 
+    #[test]
+    fn test_version_info() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (major, minor, micro) = py.version_info().unwrap();
+        assert_eq!(major, 3);
+        let expected: (u8, u8, u8) = py
+            .eval("__import__('sys').version_info[:3]", None, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!((major, minor, micro), expected);
+    }
+
     #[test]
     fn test_allow_threads_panics_safely() {
         // If -Cpanic=abort is specified, we can't catch panic.
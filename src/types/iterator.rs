@@ -38,7 +38,10 @@ impl<'p> PyIterator<'p> {
             // Returns NULL if an object cannot be iterated.
             if ptr.is_null() {
                 PyErr::fetch(py);
-                return Err(PyDowncastError);
+                return Err(PyDowncastError::new(
+                    py.from_borrowed_ptr(obj.as_ptr()),
+                    "PyIterator",
+                ));
             }
 
             if ffi::PyIter_Check(ptr) != 0 {
@@ -50,7 +53,7 @@ impl<'p> PyIterator<'p> {
                 // GILPool does not take ownership of the reference.
                 Ok(PyIterator(py.from_borrowed_ptr(ptr)))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(py.from_owned_ptr(ptr), "PyIterator"))
             }
         }
     }
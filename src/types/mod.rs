@@ -15,7 +15,7 @@ pub use self::dict::{IntoPyDict, PyDict};
 pub use self::floatob::PyFloat;
 pub use self::iterator::PyIterator;
 pub use self::list::PyList;
-pub use self::module::PyModule;
+pub use self::module::{ModuleBuilder, PyModule};
 pub use self::num::PyLong;
 pub use self::num::PyLong as PyInt;
 pub use self::sequence::PySequence;
@@ -2,7 +2,7 @@
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
-use crate::err::{PyErr, PyResult};
+use crate::err::{error_on_minusone, error_on_null, PyErr, PyResult};
 use crate::exceptions;
 use crate::ffi;
 use crate::instance::PyNativeType;
@@ -10,10 +10,13 @@ use crate::object::PyObject;
 use crate::pyclass::PyClass;
 use crate::type_object::PyTypeObject;
 use crate::types::PyTuple;
-use crate::types::{PyAny, PyDict, PyList};
-use crate::{AsPyPointer, IntoPy, Py, Python, ToPyObject};
+use crate::types::{PyAny, PyBytes, PyDict, PyList, PyString};
+use crate::{AsPyPointer, FromPyObject, IntoPy, IntoPyPointer, Py, Python, ToPyObject};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
 use std::str;
 
 /// Represents a Python `module` object.
@@ -29,12 +32,29 @@ impl PyModule {
         unsafe { py.from_owned_ptr_or_err(ffi::PyModule_New(name.as_ptr())) }
     }
 
+    /// Creates a new module object with the `__name__` attribute set to `name` and the
+    /// `__doc__` attribute set to `doc`.
+    pub fn new_with_doc<'p>(py: Python<'p>, name: &str, doc: &str) -> PyResult<&'p PyModule> {
+        let module = PyModule::new(py, name)?;
+        module.setattr("__doc__", doc)?;
+        Ok(module)
+    }
+
     /// Imports the Python module with the specified name.
     pub fn import<'p>(py: Python<'p>, name: &str) -> PyResult<&'p PyModule> {
         let name = CString::new(name)?;
         unsafe { py.from_owned_ptr_or_err(ffi::PyImport_ImportModule(name.as_ptr())) }
     }
 
+    /// Re-executes the module's source, like `importlib.reload(module)`.
+    ///
+    /// Returns the refreshed module, which is usually the same module object.
+    /// References to objects taken from the module before the reload stay valid,
+    /// but they are not updated: they still refer to the old definitions.
+    pub fn reload<'p>(&self, py: Python<'p>) -> PyResult<&'p PyModule> {
+        unsafe { py.from_owned_ptr_or_err(ffi::PyImport_ReloadModule(self.as_ptr())) }
+    }
+
     /// Loads the Python code specified into a new module.
     ///
     /// `code` is the raw Python you want to load into the module.
@@ -47,22 +67,135 @@ impl PyModule {
         file_name: &str,
         module_name: &str,
     ) -> PyResult<&'p PyModule> {
+        PyModule::exec_code(py, code, file_name, module_name, None)
+    }
+
+    /// Loads the Python code specified into a new module, whose namespace is first populated
+    /// with the contents of `globals`.
+    ///
+    /// The arguments are the same as for [from_code](PyModule::from_code). Names defined by
+    /// `code` take precedence over entries of `globals`, which itself is not modified.
+    pub fn from_code_with_globals<'p>(
+        py: Python<'p>,
+        code: &str,
+        file_name: &str,
+        module_name: &str,
+        globals: &PyDict,
+    ) -> PyResult<&'p PyModule> {
+        PyModule::exec_code(py, code, file_name, module_name, Some(globals))
+    }
+
+    /// Loads the Python code specified into a new module, without registering it in
+    /// `sys.modules`.
+    ///
+    /// The arguments are the same as for [from_code](PyModule::from_code). Unlike `from_code`,
+    /// the module is not importable by `module_name`, and loading several modules with the same
+    /// `module_name` creates independent modules.
+    pub fn from_code_isolated<'p>(
+        py: Python<'p>,
+        code: &str,
+        file_name: &str,
+        module_name: &str,
+    ) -> PyResult<&'p PyModule> {
+        let code = PyModule::compile(py, code, file_name)?;
+        let module = PyModule::new(py, module_name)?;
+        let globals = module.dict_borrowed();
+        globals.set_item("__file__", file_name)?;
+        globals.set_item("__builtins__", py.import("builtins")?)?;
+
+        unsafe {
+            let result = error_on_null(
+                py,
+                ffi::PyEval_EvalCode(code.as_ptr(), globals.as_ptr(), globals.as_ptr()),
+            )?;
+            ffi::Py_DECREF(result);
+        }
+        Ok(module)
+    }
+
+    /// Loads the Python source file at `path` into a new module.
+    ///
+    /// The path is used as the file name in tracebacks, and `module_name` is the name to give
+    /// the module. Errors reading the file are returned as the corresponding `OSError`.
+    pub fn from_path<'p>(py: Python<'p>, path: &Path, module_name: &str) -> PyResult<&'p PyModule> {
+        let code = std::fs::read_to_string(path)?;
+        PyModule::from_code(py, &code, &path.to_string_lossy(), module_name)
+    }
+
+    /// Compiles the Python code specified into a code object, without executing it.
+    ///
+    /// `code` is compiled as a module, and `file_name` is the file name to associate with
+    /// the code object. The code object can be executed with the `exec` builtin.
+    pub fn compile<'p>(py: Python<'p>, code: &str, file_name: &str) -> PyResult<&'p PyAny> {
         let data = CString::new(code)?;
         let filename = CString::new(file_name)?;
-        let module = CString::new(module_name)?;
 
         unsafe {
-            let cptr = ffi::Py_CompileString(data.as_ptr(), filename.as_ptr(), ffi::Py_file_input);
-            if cptr.is_null() {
-                return Err(PyErr::fetch(py));
+            py.from_owned_ptr_or_err(ffi::Py_CompileString(
+                data.as_ptr(),
+                filename.as_ptr(),
+                ffi::Py_file_input,
+            ))
+        }
+    }
+
+    /// Runs Python statements in the module's namespace.
+    ///
+    /// Unlike [from_code](PyModule::from_code), this executes `code` against the `__dict__` of
+    /// an existing module, so any names it binds become attributes of the module. If the
+    /// module has no `__builtins__` yet, the `builtins` module is added as `__builtins__` first.
+    pub fn run(&self, code: &str) -> PyResult<()> {
+        let py = self.py();
+        let code = PyModule::compile(py, code, "<string>")?;
+        let globals = self.dict_borrowed();
+
+        unsafe {
+            let key = CStr::from_bytes_with_nul(b"__builtins__\0").unwrap();
+            if ffi::PyDict_GetItemString(globals.as_ptr(), key.as_ptr()).is_null() {
+                let builtins = py.import("builtins")?;
+                error_on_minusone(
+                    py,
+                    ffi::PyDict_SetItemString(globals.as_ptr(), key.as_ptr(), builtins.as_ptr()),
+                )?;
             }
 
-            let mptr = ffi::PyImport_ExecCodeModuleEx(module.as_ptr(), cptr, filename.as_ptr());
-            if mptr.is_null() {
-                return Err(PyErr::fetch(py));
+            let result = error_on_null(
+                py,
+                ffi::PyEval_EvalCode(code.as_ptr(), globals.as_ptr(), globals.as_ptr()),
+            )?;
+            ffi::Py_DECREF(result);
+        }
+        Ok(())
+    }
+
+    fn exec_code<'p>(
+        py: Python<'p>,
+        code: &str,
+        file_name: &str,
+        module_name: &str,
+        globals: Option<&PyDict>,
+    ) -> PyResult<&'p PyModule> {
+        let code = PyModule::compile(py, code, file_name)?;
+        let filename = CString::new(file_name)?;
+        let module = CString::new(module_name)?;
+
+        unsafe {
+            if let Some(globals) = globals {
+                // Creates the module in `sys.modules` (returning a borrowed reference),
+                // which is then reused by `PyImport_ExecCodeModuleEx` below.
+                let mptr = error_on_null(py, ffi::PyImport_AddModule(module.as_ptr()))?;
+                error_on_minusone(
+                    py,
+                    ffi::PyDict_Update(ffi::PyModule_GetDict(mptr), globals.as_ptr()),
+                )?;
             }
 
-            <&PyModule as crate::FromPyObject>::extract(py.from_owned_ptr_or_err(mptr)?)
+            let mptr = error_on_null(
+                py,
+                ffi::PyImport_ExecCodeModuleEx(module.as_ptr(), code.as_ptr(), filename.as_ptr()),
+            )?;
+
+            <&PyModule as crate::FromPyObject>::extract(py.from_owned_ptr(mptr))
         }
     }
 
@@ -77,20 +210,22 @@ impl PyModule {
         }
     }
 
+    /// Return the dictionary object that implements module's namespace, without taking a new
+    /// reference to it.
+    ///
+    /// Unlike [dict](PyModule::dict), the returned reference borrows from `self`: a module keeps
+    /// its `__dict__` alive for as long as the module itself exists, so the dictionary cannot be
+    /// freed while the borrow is held. This avoids a reference count increment, which makes it
+    /// cheaper for quick lookups.
+    pub fn dict_borrowed(&self) -> &PyDict {
+        unsafe { &*(ffi::PyModule_GetDict(self.as_ptr()) as *const PyDict) }
+    }
+
     /// Return the index (`__all__`) of the module, creating one if needed.
     pub fn index(&self) -> PyResult<&PyList> {
-        match self.getattr("__all__") {
-            Ok(idx) => idx.downcast().map_err(PyErr::from),
-            Err(err) => {
-                if err.is_instance::<exceptions::AttributeError>(self.py()) {
-                    let l = PyList::empty(self.py());
-                    self.setattr("__all__", l).map_err(PyErr::from)?;
-                    Ok(l)
-                } else {
-                    Err(err)
-                }
-            }
-        }
+        self.get_or_set_with("__all__", || Ok(PyList::empty(self.py())))?
+            .downcast()
+            .map_err(PyErr::from)
     }
 
     unsafe fn str_from_ptr(&self, ptr: *const c_char) -> PyResult<&str> {
@@ -100,9 +235,7 @@ impl PyModule {
             let slice = CStr::from_ptr(ptr).to_bytes();
             match str::from_utf8(slice) {
                 Ok(s) => Ok(s),
-                Err(e) => Err(PyErr::from_instance(
-                    exceptions::UnicodeDecodeError::new_utf8(self.py(), slice, e)?,
-                )),
+                Err(e) => Err(PyErr::unicode_decode_error(self.py(), slice, e)?),
             }
         }
     }
@@ -114,6 +247,81 @@ impl PyModule {
         unsafe { self.str_from_ptr(ffi::PyModule_GetName(self.as_ptr())) }
     }
 
+    /// Returns the module's name, replacing invalid characters such as unpaired surrogates
+    /// with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// Unlike [name](PyModule::name), this only fails if the module does not have
+    /// a `__name__` attribute.
+    pub fn name_lossy(&self) -> PyResult<Cow<'_, str>> {
+        let name: &PyString = unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyModule_GetNameObject(self.as_ptr()))?
+        };
+        Ok(name.to_string_lossy())
+    }
+
+    /// Returns whether the module is a package, i.e. whether it has a `__path__` attribute.
+    pub fn is_package(&self) -> bool {
+        self.hasattr("__path__").unwrap_or(false)
+    }
+
+    /// Returns the source code of the module, using `inspect.getsource`.
+    ///
+    /// The source is read from the module's `__file__`, or from `linecache` if it was
+    /// registered there (e.g. for modules created with [from_code](PyModule::from_code)).
+    /// Returns `None` if the source can't be found, such as for built-in modules.
+    pub fn get_source(&self) -> PyResult<Option<String>> {
+        let py = self.py();
+        match py.import("inspect")?.call1("getsource", (self,)) {
+            Ok(source) => source.extract().map(Some),
+            Err(err) => {
+                if err.is_instance::<exceptions::OSError>(py)
+                    || err.is_instance::<exceptions::TypeError>(py)
+                {
+                    Ok(None)
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    /// Calls `visitor` on this module and, recursively, on every module it has as an attribute.
+    ///
+    /// Modules are visited depth-first in attribute order, and each module is visited only
+    /// once, even if it is reachable several times or through a cycle. Note that this also
+    /// follows modules which were merely imported, such as `sys`.
+    /// Stops at the first error returned by `visitor`.
+    pub fn walk<F>(&self, mut visitor: F) -> PyResult<()>
+    where
+        F: FnMut(&PyModule) -> PyResult<()>,
+    {
+        let mut visited = HashSet::new();
+        self.walk_inner(&mut visited, &mut visitor)
+    }
+
+    fn walk_inner(
+        &self,
+        visited: &mut HashSet<*mut ffi::PyObject>,
+        visitor: &mut dyn FnMut(&PyModule) -> PyResult<()>,
+    ) -> PyResult<()> {
+        if !visited.insert(self.as_ptr()) {
+            return Ok(());
+        }
+        visitor(self)?;
+
+        // Collect the submodules first, as `visitor` may modify the module's namespace.
+        let submodules: Vec<&PyModule> = self
+            .dict()
+            .iter()
+            .filter_map(|(_, value)| value.downcast().ok())
+            .collect();
+        for submodule in submodules {
+            submodule.walk_inner(visited, visitor)?;
+        }
+        Ok(())
+    }
+
     /// Returns the module's filename.
     ///
     /// May fail if the module does not have a `__file__` attribute.
@@ -121,6 +329,36 @@ impl PyModule {
         unsafe { self.str_from_ptr(ffi::PyModule_GetFilename(self.as_ptr())) }
     }
 
+    /// Returns the module's filename as an owned path.
+    ///
+    /// Unlike [filename](PyModule::filename), this also works for file names which are not
+    /// valid UTF-8. May fail if the module does not have a `__file__` attribute.
+    pub fn filename_path(&self) -> PyResult<PathBuf> {
+        let py = self.py();
+        unsafe {
+            let filename: &PyAny =
+                py.from_owned_ptr_or_err(ffi::PyModule_GetFilenameObject(self.as_ptr()))?;
+            // Encoding with the filesystem encoding undoes the `surrogateescape` decoding of
+            // file names which are not valid in that encoding.
+            let encoded: &PyBytes =
+                py.from_owned_ptr_or_err(ffi::PyUnicode_EncodeFSDefault(filename.as_ptr()))?;
+            path_from_fs_bytes(encoded.as_bytes())
+        }
+    }
+
+    /// Calls a function in the module with positional arguments taken from a slice.
+    ///
+    /// This is equivalent to the Python expression `module.name(*args, **kwargs)`, and is
+    /// useful when the number of arguments is only known at runtime.
+    pub fn call_slice(
+        &self,
+        name: &str,
+        args: &[&PyAny],
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<&PyAny> {
+        self.call(name, PyTuple::new(self.py(), args), kwargs)
+    }
+
     /// Calls a function in the module.
     ///
     /// This is equivalent to the Python expression `module.name(*args, **kwargs)`.
@@ -133,6 +371,19 @@ impl PyModule {
         self.getattr(name)?.call(args, kwargs)
     }
 
+    /// Calls a function in the module, returning an owned reference to the result.
+    ///
+    /// This is equivalent to [`call`](#method.call), but the result is not tied to the
+    /// lifetime of the module reference, so it can outlive a temporary module.
+    pub fn call_owned(
+        &self,
+        name: &str,
+        args: impl IntoPy<Py<PyTuple>>,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<PyObject> {
+        self.call(name, args, kwargs).map(PyObject::from)
+    }
+
     /// Calls a function in the module with only positional arguments.
     ///
     /// This is equivalent to the Python expression `module.name(*args)`.
@@ -154,19 +405,194 @@ impl PyModule {
         self.getattr(name)
     }
 
+    /// Gets a member from the module, returning an owned reference to it.
+    ///
+    /// This is equivalent to [get](PyModule::get), but the result is not tied to the
+    /// lifetime of the module reference, so it can outlive a temporary module.
+    pub fn get_owned(&self, name: &str) -> PyResult<PyObject> {
+        self.getattr(name).map(PyObject::from)
+    }
+
+    /// Gets a member from the module, or `None` if the module has no such member.
+    ///
+    /// Unlike [get](PyModule::get), a missing member is not an error; any error other
+    /// than an `AttributeError` is still returned.
+    pub fn get_opt(&self, name: &str) -> PyResult<Option<&PyAny>> {
+        match self.getattr(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => {
+                if err.is_instance::<exceptions::AttributeError>(self.py()) {
+                    Ok(None)
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    /// Gets a member directly from the module's `__dict__`, or `None` if it has no such entry.
+    ///
+    /// Unlike [get](PyModule::get), this doesn't go through the attribute protocol: the stored
+    /// object is returned as is, without invoking descriptors on the module's type or the
+    /// module's `__getattr__`. Errors raised while looking up `name`, e.g. by the `__eq__` of a
+    /// non-string key in the `__dict__`, are returned.
+    pub fn get_raw(&self, name: &str) -> PyResult<Option<&PyAny>> {
+        let py = self.py();
+        let name = PyString::new(py, name);
+        unsafe {
+            let ptr = ffi::PyDict_GetItemWithError(self.dict_borrowed().as_ptr(), name.as_ptr());
+            if ptr.is_null() {
+                return match PyErr::take(py) {
+                    Some(err) => Err(err),
+                    None => Ok(None),
+                };
+            }
+            // PyDict_GetItemWithError returns a borrowed ptr, must make it owned for safety.
+            ffi::Py_INCREF(ptr);
+            Ok(Some(py.from_owned_ptr(ptr)))
+        }
+    }
+
+    /// Gets a member from the module, or sets it to the value returned by `init` if the module
+    /// has no such member.
+    ///
+    /// This is useful for caching objects on a module. `init` is only called if getting the
+    /// member fails with an `AttributeError`; other errors are returned. The new member is not
+    /// added to `__all__`.
+    pub fn get_or_set_with<V, F>(&self, name: &str, init: F) -> PyResult<&PyAny>
+    where
+        V: ToPyObject,
+        F: FnOnce() -> PyResult<V>,
+    {
+        if let Some(value) = self.get_opt(name)? {
+            return Ok(value);
+        }
+        let value = init()?.to_object(self.py());
+        self.setattr(name, &value)?;
+        unsafe { Ok(self.py().from_owned_ptr(value.into_ptr())) }
+    }
+
+    /// Gets a member from the module and extracts it into `T`.
+    ///
+    /// This is equivalent to `module.get(name)?.extract()`.
+    pub fn extract_attr<'p, T>(&'p self, name: &str) -> PyResult<T>
+    where
+        T: FromPyObject<'p>,
+    {
+        self.getattr(name)?.extract()
+    }
+
+    /// Returns the sorted names of the module's attributes, including dunder names.
+    ///
+    /// This is equivalent to the Python expression `dir(module)`.
+    /// Returns an error if a name is not a valid UTF-8 string.
+    pub fn dir(&self) -> PyResult<Vec<String>> {
+        let names: &PyList = unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyObject_Dir(self.as_ptr()))?
+        };
+        names.iter().map(|name| name.extract()).collect()
+    }
+
+    /// Returns the `(name, value)` pairs of the module's namespace, skipping dunder names
+    /// such as `__name__` or `__doc__`.
+    ///
+    /// Returns an error if the namespace contains a name which is not a valid UTF-8 string.
+    pub fn items(&self) -> PyResult<Vec<(&str, &PyAny)>> {
+        let mut items = Vec::new();
+        for (name, value) in self.dict() {
+            let name: &str = name.extract()?;
+            if !(name.starts_with("__") && name.ends_with("__")) {
+                items.push((name, value));
+            }
+        }
+        Ok(items)
+    }
+
     /// Adds a member to the module.
     ///
     /// This is a convenience function which can be used from the module's initialization function.
+    /// The name is appended to `__all__` unless it is already listed there.
     pub fn add<V>(&self, name: &str, value: V) -> PyResult<()>
     where
         V: ToPyObject,
     {
-        self.index()?
-            .append(name)
-            .expect("could not append __name__ to __all__");
+        append_to_index(self.index()?, name)?;
+        self.setattr(name, value)
+    }
+
+    /// Adds an existing Python object to the module.
+    ///
+    /// This behaves like [add](PyModule::add), but registers `value` directly instead of going
+    /// through a `ToPyObject` conversion.
+    pub fn add_object(&self, name: &str, value: &PyAny) -> PyResult<()> {
+        self.add(name, value)
+    }
+
+    /// Adds a member to the module, converting it with `IntoPy` rather than `ToPyObject`.
+    ///
+    /// This behaves like [add](PyModule::add), but consumes `value`, so it also works for
+    /// types which can only be moved into Python.
+    pub fn add_into<V>(&self, name: &str, value: V) -> PyResult<()>
+    where
+        V: IntoPy<PyObject>,
+    {
+        self.add(name, value.into_py(self.py()))
+    }
+
+    /// Adds several members to the module.
+    ///
+    /// This is equivalent to calling [add](PyModule::add) for each item, but `__all__` is only
+    /// looked up once.
+    /// If adding an item fails, the error is returned and the items added before it are left
+    /// in place.
+    pub fn add_all<'a, I, V>(&self, items: I) -> PyResult<()>
+    where
+        I: IntoIterator<Item = (&'a str, V)>,
+        V: ToPyObject,
+    {
+        let index = self.index()?;
+        for (name, value) in items {
+            append_to_index(index, name)?;
+            self.setattr(name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a member to the module without listing it in `__all__`.
+    ///
+    /// Unlike [add](PyModule::add), the member is not exported by `from module import *`.
+    /// This is useful for private helpers or cached objects which should still be reachable
+    /// as attributes of the module.
+    pub fn add_private<V>(&self, name: &str, value: V) -> PyResult<()>
+    where
+        V: ToPyObject,
+    {
         self.setattr(name, value)
     }
 
+    /// Removes a member from the module, and from `__all__` if it is listed there.
+    ///
+    /// This is the inverse of [add](PyModule::add). Returns `false` if the module has no such
+    /// member; any error other than an `AttributeError` is returned.
+    pub fn remove(&self, name: &str) -> PyResult<bool> {
+        // Delete the member first, so that `__all__` is left alone if that fails.
+        let removed = match self.delattr(name) {
+            Ok(()) => true,
+            Err(err) => {
+                if err.is_instance::<exceptions::AttributeError>(self.py()) {
+                    false
+                } else {
+                    return Err(err);
+                }
+            }
+        };
+        if let Some(index) = self.get_opt("__all__")? {
+            remove_from_index(index.downcast()?, name)?;
+        }
+        Ok(removed)
+    }
+
     /// Adds a new extension type to the module.
     ///
     /// This is a convenience function that initializes the `class`,
@@ -176,7 +602,110 @@ impl PyModule {
     where
         T: PyClass,
     {
-        self.add(T::NAME, <T as PyTypeObject>::type_object(self.py()))
+        self.add_class_opt::<T>(true)
+    }
+
+    /// Adds a new extension type to the module, listing it in `__all__` only if `export` is
+    /// true.
+    ///
+    /// With `export` set to false, the class is added like [add_private](PyModule::add_private)
+    /// would: it is an attribute of the module but isn't imported by `from module import *`.
+    pub fn add_class_opt<T>(&self, export: bool) -> PyResult<()>
+    where
+        T: PyClass,
+    {
+        let ty = <T as PyTypeObject>::type_object(self.py());
+        if export {
+            self.add(T::NAME, ty)
+        } else {
+            self.add_private(T::NAME, ty)
+        }
+    }
+
+    /// Adds a new extension type to the module under `name` instead of `T::NAME`.
+    ///
+    /// This can be used to expose a class under a different public name, or in several modules.
+    /// Only the module attribute is renamed: the type's own `__name__` and `__qualname__`, as
+    /// well as `__module__` (set by `#[pyclass(module = "...")]`), are unchanged, so `repr()`
+    /// and pickling still refer to the original name.
+    pub fn add_class_named<T>(&self, name: &str) -> PyResult<()>
+    where
+        T: PyClass,
+    {
+        self.add(name, <T as PyTypeObject>::type_object(self.py()))
+    }
+
+    /// Adds a submodule to this module.
+    ///
+    /// The submodule is added as an attribute named after its `__name__`, and is also
+    /// registered in `sys.modules` as `<parent name>.<submodule name>`, so that
+    /// `import parent.child` works once the parent module is importable.
+    pub fn add_submodule(&self, submodule: &PyModule) -> PyResult<()> {
+        let name = submodule.name()?;
+        let full_name = format!("{}.{}", self.name()?, name);
+        self.register_submodule(&full_name, name, submodule)
+    }
+
+    /// Creates a submodule named `<parent name>.<name>` and adds it to this module.
+    ///
+    /// `builder` is called to populate the submodule before it is registered in `sys.modules`
+    /// and added as an attribute named `name`, so nothing is registered if it fails.
+    ///
+    /// ```rust,ignore
+    /// m.create_submodule("utils", |utils| utils.add_wrapped(wrap_pyfunction!(double)))?;
+    /// ```
+    pub fn create_submodule(
+        &self,
+        name: &str,
+        builder: impl FnOnce(&PyModule) -> PyResult<()>,
+    ) -> PyResult<()> {
+        let full_name = format!("{}.{}", self.name()?, name);
+        let submodule = PyModule::new(self.py(), &full_name)?;
+        builder(submodule)?;
+        self.register_submodule(&full_name, name, submodule)
+    }
+
+    /// Returns the submodule at the dotted path `dotted`, relative to this module,
+    /// creating any missing modules along the way.
+    ///
+    /// For example, `root.ensure_submodule("a.b")` returns `root.a.b`. Missing modules are
+    /// created, registered in `sys.modules` under their fully qualified names and added to
+    /// their parent, as with [create_submodule](PyModule::create_submodule); existing ones
+    /// are reused. Returns a `TypeError` if an existing attribute on the path is not a module.
+    pub fn ensure_submodule(&self, dotted: &str) -> PyResult<&PyModule> {
+        let mut module = self;
+        for name in dotted.split('.') {
+            if name.is_empty() {
+                return Err(exceptions::ValueError::py_err(format!(
+                    "invalid submodule path '{}'",
+                    dotted
+                )));
+            }
+            module = match module.get_opt(name)? {
+                Some(existing) => existing.downcast()?,
+                None => {
+                    let full_name = format!("{}.{}", module.name()?, name);
+                    let submodule = PyModule::new(self.py(), &full_name)?;
+                    module.register_submodule(&full_name, name, submodule)?;
+                    submodule
+                }
+            };
+        }
+        Ok(module)
+    }
+
+    /// Registers `submodule` in `sys.modules` as `full_name`, and adds it to this module.
+    fn register_submodule(
+        &self,
+        full_name: &str,
+        name: &str,
+        submodule: &PyModule,
+    ) -> PyResult<()> {
+        self.py()
+            .import("sys")?
+            .get("modules")?
+            .set_item(full_name, submodule)?;
+        self.add(name, submodule)
     }
 
     /// Adds a function or a (sub)module to a module, using the functions __name__ as name.
@@ -189,16 +718,149 @@ impl PyModule {
     /// m.add_wrapped(wrap_pymodule!(utils));
     /// ```
     ///
-    /// You can also add a function with a custom name using [add](PyModule::add):
+    /// Returns an `AttributeError` if the wrapped object does not have a `__name__`.
+    ///
+    /// You can also add a function with a custom name using
+    /// [add_wrapped_as](PyModule::add_wrapped_as).
+    pub fn add_wrapped(&self, wrapper: &impl Fn(Python) -> PyObject) -> PyResult<()> {
+        let py = self.py();
+        let function = wrapper(py);
+        let name: String = function.getattr(py, "__name__")?.extract(py)?;
+        // The wrapper is only called once, as wrapping a module initializes it.
+        self.add_wrapped_as(&name, &|py| function.clone_ref(py))
+    }
+
+    /// Adds a function or a (sub)module to a module, using `name` instead of its `__name__`.
+    ///
+    /// Unlike [add_wrapped](PyModule::add_wrapped), this doesn't need the wrapped object to
+    /// have a `__name__`. The name is still added to `__all__`.
     ///
     /// ```rust,ignore
-    /// m.add("also_double", wrap_pyfunction!(double)(py));
+    /// m.add_wrapped_as("also_double", &wrap_pyfunction!(double))?;
     /// ```
-    pub fn add_wrapped(&self, wrapper: &impl Fn(Python) -> PyObject) -> PyResult<()> {
-        let function = wrapper(self.py());
-        let name = function
-            .getattr(self.py(), "__name__")
-            .expect("A function or module must have a __name__");
-        self.add(name.extract(self.py()).unwrap(), function)
+    pub fn add_wrapped_as(
+        &self,
+        name: &str,
+        wrapper: &impl Fn(Python) -> PyObject,
+    ) -> PyResult<()> {
+        self.add(name, wrapper(self.py()))
+    }
+
+    /// Adds a wrapped function to a module under a custom name.
+    ///
+    /// This is the same as [add_wrapped_as](PyModule::add_wrapped_as).
+    ///
+    /// ```rust,ignore
+    /// m.add_function("also_double", &wrap_pyfunction!(double))?;
+    /// ```
+    pub fn add_function(&self, name: &str, wrapper: &impl Fn(Python) -> PyObject) -> PyResult<()> {
+        self.add_wrapped_as(name, wrapper)
+    }
+}
+
+/// Adds the contents of a module with chained calls.
+///
+/// Each method adds one member to the module, using the corresponding `PyModule` method.
+///
+/// ```rust,ignore
+/// #[pymodule]
+/// fn mymodule(_py: Python, m: &PyModule) -> PyResult<()> {
+///     ModuleBuilder::new(m)
+///         .class::<MyClass>()?
+///         .function(&wrap_pyfunction!(double))?
+///         .constant("VERSION", "1.0")?
+///         .submodule("utils", |utils| {
+///             utils.function(&wrap_pyfunction!(triple))?;
+///             Ok(())
+///         })?;
+///     Ok(())
+/// }
+/// ```
+pub struct ModuleBuilder<'a> {
+    module: &'a PyModule,
+}
+
+impl<'a> ModuleBuilder<'a> {
+    /// Creates a builder adding members to `module`.
+    pub fn new(module: &'a PyModule) -> Self {
+        ModuleBuilder { module }
+    }
+
+    /// Adds an extension type, see [PyModule::add_class].
+    pub fn class<T>(&mut self) -> PyResult<&mut Self>
+    where
+        T: PyClass,
+    {
+        self.module.add_class::<T>()?;
+        Ok(self)
+    }
+
+    /// Adds a wrapped function or module, see [PyModule::add_wrapped].
+    pub fn function(&mut self, wrapper: &impl Fn(Python) -> PyObject) -> PyResult<&mut Self> {
+        self.module.add_wrapped(wrapper)?;
+        Ok(self)
+    }
+
+    /// Adds a member, see [PyModule::add].
+    pub fn constant<V>(&mut self, name: &str, value: V) -> PyResult<&mut Self>
+    where
+        V: ToPyObject,
+    {
+        self.module.add(name, value)?;
+        Ok(self)
+    }
+
+    /// Creates a submodule populated by `build`, see [PyModule::create_submodule].
+    pub fn submodule<F>(&mut self, name: &str, build: F) -> PyResult<&mut Self>
+    where
+        F: FnOnce(&mut ModuleBuilder) -> PyResult<()>,
+    {
+        self.module
+            .create_submodule(name, |submodule| build(&mut ModuleBuilder::new(submodule)))?;
+        Ok(self)
+    }
+
+    /// Returns the module being built.
+    pub fn finish(&self) -> &'a PyModule {
+        self.module
+    }
+}
+
+/// Appends `name` to `index` unless it is already listed there.
+fn append_to_index(index: &PyList, name: &str) -> PyResult<()> {
+    // Entries which aren't strings can't be equal to `name`, so they are skipped.
+    let already_listed = index
+        .iter()
+        .any(|item| item.extract::<&str>().ok() == Some(name));
+    if already_listed {
+        Ok(())
+    } else {
+        index.append(name)
+    }
+}
+
+/// Converts a file name encoded with the filesystem encoding to a path.
+#[cfg(unix)]
+fn path_from_fs_bytes(bytes: &[u8]) -> PyResult<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(Path::new(std::ffi::OsStr::from_bytes(bytes)).to_path_buf())
+}
+
+/// Converts a file name encoded with the filesystem encoding to a path.
+///
+/// The filesystem encoding is UTF-8 on other platforms.
+#[cfg(not(unix))]
+fn path_from_fs_bytes(bytes: &[u8]) -> PyResult<PathBuf> {
+    Ok(PathBuf::from(str::from_utf8(bytes)?))
+}
+
+/// Removes `name` from `index`, if it is listed there.
+fn remove_from_index(index: &PyList, name: &str) -> PyResult<()> {
+    let position = index
+        .iter()
+        .position(|item| item.extract::<&str>().ok() == Some(name));
+    match position {
+        Some(position) => index.del_item(position),
+        None => Ok(()),
     }
 }
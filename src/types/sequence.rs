@@ -378,7 +378,7 @@ impl<'v> PyTryFrom<'v> for PySequence {
             if ffi::PySequence_Check(value.as_ptr()) != 0 {
                 Ok(<PySequence as PyTryFrom>::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, "PySequence"))
             }
         }
     }
@@ -92,3 +92,54 @@ fn test_exception_nosegfault() {
     assert!(io_err().is_err());
     assert!(parse_int().is_err());
 }
+
+pyo3::create_exception!(test_exceptions, ParseFailure, exceptions::Exception);
+
+#[derive(Debug)]
+struct ParseError {
+    line: usize,
+    reason: &'static str,
+}
+
+impl pyo3::PyErrException for ParseError {
+    fn to_exception(self, py: Python) -> PyResult<PyObject> {
+        let exception = py.get_type::<ParseFailure>().call1((self.reason,))?;
+        exception.setattr("line", self.line)?;
+        Ok(exception.into())
+    }
+}
+
+fn parse_config() -> Result<(), ParseError> {
+    Err(ParseError {
+        line: 3,
+        reason: "unexpected token",
+    })
+}
+
+#[pyfunction]
+fn load_config() -> PyResult<()> {
+    parse_config()?;
+    Ok(())
+}
+
+#[test]
+fn test_pyerr_exception() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let load_config = wrap_pyfunction!(load_config)(py);
+    let parse_failure = py.get_type::<ParseFailure>();
+
+    py_run!(
+        py,
+        load_config parse_failure,
+        r#"
+        try:
+            load_config()
+        except parse_failure as e:
+            assert str(e) == "unexpected token"
+            assert e.line == 3
+        else:
+            assert False, "ParseFailure was not raised"
+        "#
+    );
+}
@@ -285,3 +285,989 @@ fn test_vararg_module() {
     py_assert!(py, m, "m.int_vararg_fn() == [5, ()]");
     py_assert!(py, m, "m.int_vararg_fn(1, 2) == [1, (2,)]");
 }
+
+#[test]
+fn test_add_submodule() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let parent = PyModule::new(py, "add_submodule_parent").unwrap();
+    let child = PyModule::new(py, "child").unwrap();
+    child.add("value", 42).unwrap();
+    parent.add_submodule(child).unwrap();
+
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    sys_modules
+        .set_item("add_submodule_parent", parent)
+        .unwrap();
+
+    let module = PyModule::from_code(
+        py,
+        "import add_submodule_parent.child as c\nvalue = c.value",
+        "import_child.py",
+        "import_child",
+    )
+    .map_err(|e| e.print(py))
+    .unwrap();
+    let value: i32 = module.get("value").unwrap().extract().unwrap();
+    assert_eq!(value, 42);
+    py_assert!(py, parent, "parent.child.value == 42");
+    py_assert!(py, parent, "'child' in parent.__all__");
+}
+
+#[test]
+fn test_add_submodule_nameless_parent() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let parent: &PyModule = py
+        .eval(
+            "type(__import__('sys')).__new__(type(__import__('sys')))",
+            None,
+            None,
+        )
+        .unwrap()
+        .downcast()
+        .unwrap();
+    let child = PyModule::new(py, "child").unwrap();
+    assert!(parent.add_submodule(child).is_err());
+}
+
+#[test]
+fn test_add_does_not_duplicate_all() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_twice").unwrap();
+    module.add("x", 1).unwrap();
+    module.index().unwrap().append(2).unwrap();
+    module.add("x", 2).unwrap();
+
+    py_assert!(py, module, "module.__all__ == ['x', 2]");
+    py_assert!(py, module, "module.x == 2");
+}
+
+#[test]
+fn test_add_private() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_private").unwrap();
+    module.add("public", 1).unwrap();
+    module.add_private("_cache", 2).unwrap();
+
+    let cache: i32 = module.getattr("_cache").unwrap().extract().unwrap();
+    assert_eq!(cache, 2);
+    py_assert!(py, module, "module.__all__ == ['public']");
+}
+
+#[test]
+fn test_add_wrapped_without_name() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_wrapped_without_name").unwrap();
+    let err = module
+        .add_wrapped(&|py: Python| 42.to_object(py))
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::AttributeError>(py));
+}
+
+#[test]
+fn test_module_from_code_with_globals() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let globals = [("base", 40), ("shadowed", 0)].into_py_dict(py);
+    let module = PyModule::from_code_with_globals(
+        py,
+        "shadowed = 2\ndef add(x):\n    return base + x",
+        "globals_mod.py",
+        "globals_mod",
+        globals,
+    )
+    .map_err(|e| e.print(py))
+    .unwrap();
+
+    let ret: i32 = module.call1("add", (2,)).unwrap().extract().unwrap();
+    assert_eq!(ret, 42);
+    py_assert!(py, module, "module.shadowed == 2");
+    py_assert!(py, globals, "globals['shadowed'] == 0");
+}
+
+#[test]
+fn test_module_from_path() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let path = std::env::temp_dir().join(format!("pyo3_from_path_{}.py", std::process::id()));
+    std::fs::write(&path, "def triple(x):\n    return 3 * x\n").unwrap();
+    let module = PyModule::from_path(py, &path, "from_path_mod");
+    std::fs::remove_file(&path).unwrap();
+
+    let module = module.map_err(|e| e.print(py)).unwrap();
+    let ret: i32 = module.call1("triple", (2,)).unwrap().extract().unwrap();
+    assert_eq!(ret, 6);
+
+    let err = PyModule::from_path(py, &path, "from_path_missing").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::FileNotFoundError>(py));
+}
+
+#[test]
+fn test_module_get_opt() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::from_code(
+        py,
+        "present = 1\ndef __getattr__(name):\n    if name == 'broken':\n        raise ValueError(name)\n    raise AttributeError(name)",
+        "get_opt_mod.py",
+        "get_opt_mod",
+    )
+    .unwrap();
+
+    let present: i32 = module
+        .get_opt("present")
+        .unwrap()
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(present, 1);
+    assert!(module.get_opt("absent").unwrap().is_none());
+    let err = module.get_opt("broken").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+}
+
+#[test]
+fn test_module_dict_borrowed() {
+    use pyo3::AsPyPointer;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "dict_borrowed").unwrap();
+    let dict = module.dict_borrowed();
+    let name: &str = dict.get_item("__name__").unwrap().extract().unwrap();
+    assert_eq!(name, "dict_borrowed");
+    assert_eq!(dict.as_ptr(), module.dict().as_ptr());
+}
+
+#[test]
+fn test_module_items() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::from_code(
+        py,
+        "def first():\n    pass\ndef second():\n    pass\nCONSTANT = 3",
+        "items_mod.py",
+        "items_mod",
+    )
+    .unwrap();
+
+    let items = module.items().unwrap();
+    let names: Vec<&str> = items.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, vec!["first", "second", "CONSTANT"]);
+    assert!(items[0].1.is_callable());
+    assert_eq!(items[2].1.extract::<i32>().unwrap(), 3);
+
+    module.dict().set_item(1, "not a str").unwrap();
+    assert!(module.items().is_err());
+}
+
+fn call_scale(py: Python) -> PyObject {
+    let module = PyModule::from_code(
+        py,
+        "def scale(x, factor=1):\n    return x * factor",
+        "call_owned.py",
+        "call_owned",
+    )
+    .unwrap();
+    let kwargs = [("factor", 3)].into_py_dict(py);
+    module.call_owned("scale", (2,), Some(kwargs)).unwrap()
+}
+
+#[test]
+fn test_module_call_owned() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let result = call_scale(py);
+    assert_eq!(result.extract::<i32>(py).unwrap(), 6);
+}
+
+#[test]
+fn test_module_reload() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let dir = std::env::temp_dir().join(format!("pyo3_reload_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("reload_mod.py");
+    std::fs::write(&path, "VALUE = 1\n").unwrap();
+
+    let sys_path: &pyo3::types::PyList = py
+        .import("sys")
+        .unwrap()
+        .get("path")
+        .unwrap()
+        .downcast()
+        .unwrap();
+    sys_path.insert(0, dir.to_str().unwrap()).unwrap();
+
+    let module = PyModule::import(py, "reload_mod").unwrap();
+    let old_value = module.get("VALUE").unwrap();
+    assert_eq!(old_value.extract::<i32>().unwrap(), 1);
+
+    std::fs::write(&path, "VALUE = 1000\n").unwrap();
+    let reloaded = module.reload(py);
+    std::fs::write(&path, "raise ValueError('broken')\n").unwrap();
+    let broken = module.reload(py);
+
+    sys_path.del_item(0).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let reloaded = reloaded.map_err(|e| e.print(py)).unwrap();
+    let value: i32 = reloaded.get("VALUE").unwrap().extract().unwrap();
+    assert_eq!(value, 1000);
+    assert_eq!(old_value.extract::<i32>().unwrap(), 1);
+
+    let err = broken.unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+}
+
+#[test]
+fn test_module_add_all() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_all").unwrap();
+    module
+        .add_all(vec![
+            ("RED", 1),
+            ("GREEN", 2),
+            ("BLUE", 3),
+            ("CYAN", 4),
+            ("MAGENTA", 5),
+        ])
+        .unwrap();
+
+    py_assert!(py, module, "module.RED == 1 and module.MAGENTA == 5");
+    py_assert!(
+        py,
+        module,
+        "module.__all__ == ['RED', 'GREEN', 'BLUE', 'CYAN', 'MAGENTA']"
+    );
+}
+
+#[test]
+fn test_create_submodule() {
+    use pyo3::wrap_pyfunction;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let parent = PyModule::new(py, "create_submodule_parent").unwrap();
+    parent
+        .create_submodule("sub", |sub| sub.add_wrapped(wrap_pyfunction!(double)))
+        .unwrap();
+    assert!(parent
+        .create_submodule("failed", |_| Err(pyo3::exceptions::ValueError::py_err(
+            "oops"
+        )))
+        .is_err());
+
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    sys_modules
+        .set_item("create_submodule_parent", parent)
+        .unwrap();
+
+    let module = PyModule::from_code(
+        py,
+        "import create_submodule_parent.sub as s\nvalue = s.double(21)",
+        "import_sub.py",
+        "import_sub",
+    )
+    .map_err(|e| e.print(py))
+    .unwrap();
+    let value: i32 = module.get("value").unwrap().extract().unwrap();
+    assert_eq!(value, 42);
+    py_assert!(
+        py,
+        parent,
+        "parent.sub.__name__ == 'create_submodule_parent.sub'"
+    );
+    py_assert!(py, parent, "not hasattr(parent, 'failed')");
+    py_assert!(
+        py,
+        sys_modules,
+        "'create_submodule_parent.failed' not in sys_modules"
+    );
+}
+
+#[test]
+fn test_module_extract_attr() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::from_code(
+        py,
+        "PORT = 8080\nHOST = 'localhost'",
+        "extract_attr.py",
+        "extract_attr",
+    )
+    .unwrap();
+
+    assert_eq!(module.extract_attr::<i64>("PORT").unwrap(), 8080);
+    assert_eq!(module.extract_attr::<String>("HOST").unwrap(), "localhost");
+
+    let err = module.extract_attr::<i64>("HOST").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+    let err = module.extract_attr::<i64>("MISSING").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::AttributeError>(py));
+}
+
+#[test]
+fn test_module_dir() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::from_code(
+        py,
+        "def greet():\n    pass\nANSWER = 42",
+        "dir_mod.py",
+        "dir_mod",
+    )
+    .unwrap();
+
+    let names = module.dir().unwrap();
+    assert!(names.contains(&"greet".to_string()));
+    assert!(names.contains(&"ANSWER".to_string()));
+    assert!(names.contains(&"__name__".to_string()));
+
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+}
+
+#[test]
+fn test_module_add_function() {
+    use pyo3::wrap_pyfunction;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_function").unwrap();
+    module
+        .add_function("also_double", &wrap_pyfunction!(double))
+        .unwrap();
+
+    py_assert!(py, module, "module.also_double(21) == 42");
+    py_assert!(py, module, "module.__all__ == ['also_double']");
+    py_assert!(py, module, "not hasattr(module, 'double')");
+}
+
+#[test]
+fn test_module_add_wrapped_as() {
+    use pyo3::wrap_pyfunction;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_wrapped_as").unwrap();
+    module
+        .add_wrapped_as("also_double", &wrap_pyfunction!(double))
+        .unwrap();
+
+    py_assert!(py, module, "module.also_double(21) == 42");
+    py_assert!(py, module, "module.__all__ == ['also_double']");
+    py_assert!(py, module, "not hasattr(module, 'double')");
+
+    // The wrapped object doesn't need a `__name__`.
+    module
+        .add_wrapped_as("answer", &|py| 42.to_object(py))
+        .unwrap();
+    py_assert!(py, module, "module.answer == 42");
+}
+
+#[test]
+fn test_module_add_wrapped() {
+    use pyo3::wrap_pyfunction;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_wrapped").unwrap();
+    module.add_wrapped(&wrap_pyfunction!(double)).unwrap();
+    py_assert!(py, module, "module.double(21) == 42");
+    py_assert!(py, module, "module.__all__ == ['double']");
+}
+
+#[test]
+fn test_module_name_lossy() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "name_lossy").unwrap();
+    assert_eq!(module.name_lossy().unwrap(), module.name().unwrap());
+
+    let name = py.eval("'bad\\udc80'", None, None).unwrap();
+    module.setattr("__name__", name).unwrap();
+    assert!(module.name().is_err());
+    let lossy = module.name_lossy().unwrap();
+    assert!(lossy.starts_with("bad"));
+    assert!(lossy.contains('\u{FFFD}'));
+}
+
+#[test]
+fn test_module_compile() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let code = PyModule::compile(py, "x = base * 2", "compiled.py").unwrap();
+    let builtins = py.import("builtins").unwrap();
+
+    for &base in &[1, 21] {
+        let globals = [("base", base)].into_py_dict(py);
+        builtins.call1("exec", (code, globals)).unwrap();
+        let x: i32 = globals.get_item("x").unwrap().extract().unwrap();
+        assert_eq!(x, base * 2);
+    }
+
+    let err = PyModule::compile(py, "def broken(:", "broken.py").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::SyntaxError>(py));
+}
+
+#[test]
+fn test_module_ensure_submodule() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let root = PyModule::new(py, "ensure_root").unwrap();
+    let a = PyModule::new(py, "ensure_root.a").unwrap();
+    a.add("existing", true).unwrap();
+    root.add("a", a).unwrap();
+
+    let leaf = root.ensure_submodule("a.b").unwrap();
+    leaf.add("value", 42).unwrap();
+    assert_eq!(leaf.name().unwrap(), "ensure_root.a.b");
+    assert_eq!(
+        root.ensure_submodule("a.b").unwrap().as_ref() as *const PyAny,
+        leaf.as_ref() as *const PyAny
+    );
+
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    sys_modules.set_item("ensure_root", root).unwrap();
+    sys_modules.set_item("ensure_root.a", a).unwrap();
+
+    let module = PyModule::from_code(
+        py,
+        "import ensure_root.a.b as b\nvalue = b.value",
+        "import_leaf.py",
+        "import_leaf",
+    )
+    .map_err(|e| e.print(py))
+    .unwrap();
+    let value: i32 = module.get("value").unwrap().extract().unwrap();
+    assert_eq!(value, 42);
+    py_assert!(py, root, "root.a.existing and root.a.b.value == 42");
+
+    root.add("not_a_module", 1).unwrap();
+    assert!(root.ensure_submodule("not_a_module.c").is_err());
+    assert!(root.ensure_submodule("a..b").is_err());
+}
+
+fn load_square(py: Python) -> PyObject {
+    let module = PyModule::from_code(
+        py,
+        "def square(x):\n    return x * x",
+        "get_owned.py",
+        "get_owned",
+    )
+    .unwrap();
+    module.get_owned("square").unwrap()
+}
+
+#[test]
+fn test_module_get_owned() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let square = load_square(py);
+    let result: i32 = square.call1(py, (7,)).unwrap().extract(py).unwrap();
+    assert_eq!(result, 49);
+}
+
+#[test]
+fn test_module_from_code_isolated() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let first =
+        PyModule::from_code_isolated(py, "VALUE = 1", "first.py", "isolated_snippet").unwrap();
+    let second =
+        PyModule::from_code_isolated(py, "VALUE = 2", "second.py", "isolated_snippet").unwrap();
+
+    assert_eq!(first.extract_attr::<i32>("VALUE").unwrap(), 1);
+    assert_eq!(second.extract_attr::<i32>("VALUE").unwrap(), 2);
+    assert_eq!(first.filename().unwrap(), "first.py");
+
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    py_assert!(py, sys_modules, "'isolated_snippet' not in sys_modules");
+
+    let err = PyModule::from_code_isolated(py, "raise KeyError('k')", "err.py", "isolated_err")
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::KeyError>(py));
+}
+
+#[test]
+fn test_module_is_package() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "plain_module").unwrap();
+    assert!(!module.is_package());
+
+    let package = PyModule::new(py, "synthesized_package").unwrap();
+    package
+        .setattr("__path__", pyo3::types::PyList::empty(py))
+        .unwrap();
+    assert!(package.is_package());
+}
+
+#[test]
+fn test_module_add_object() {
+    use pyo3::AsPyPointer;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_object").unwrap();
+    let list = pyo3::types::PyList::new(py, &[1, 2, 3]);
+    module.add_object("numbers", list).unwrap();
+
+    let numbers = module.get("numbers").unwrap();
+    assert_eq!(numbers.as_ptr(), list.as_ptr());
+    py_assert!(py, module, "module.numbers == [1, 2, 3]");
+    py_assert!(py, module, "module.__all__ == ['numbers']");
+}
+
+#[test]
+fn test_module_run() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::from_code(py, "y = 1", "run.py", "run").unwrap();
+    module.run("x = 5\ny += x").unwrap();
+
+    assert_eq!(module.get("x").unwrap().extract::<i32>().unwrap(), 5);
+    assert_eq!(module.get("y").unwrap().extract::<i32>().unwrap(), 6);
+
+    let err = module.run("raise ValueError('from run')").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+
+    let module = PyModule::new(py, "run_builtins").unwrap();
+    module.run("n = len([1, 2, 3])").unwrap();
+    assert_eq!(module.get("n").unwrap().extract::<usize>().unwrap(), 3);
+    py_assert!(py, module, "module.__builtins__ is __import__('builtins')");
+}
+
+#[test]
+fn test_module_add_class_named() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_class_named").unwrap();
+    module.add_class_named::<ValueClass>("Alias").unwrap();
+    module.run("instance = Alias(7)").unwrap();
+
+    let instance: &PyCell<ValueClass> = module.get("instance").unwrap().downcast().unwrap();
+    assert_eq!(instance.borrow().value, 7);
+    assert!(module.get("ValueClass").is_err());
+    py_assert!(py, module, "module.__all__ == ['Alias']");
+    py_assert!(py, module, "module.Alias.__name__ == 'ValueClass'");
+}
+
+#[test]
+fn test_module_filename_path() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let path = std::env::temp_dir().join(format!("pyo3_filename_path_{}.py", std::process::id()));
+    std::fs::write(&path, "x = 1\n").unwrap();
+    let module = PyModule::from_path(py, &path, "filename_path_mod");
+    std::fs::remove_file(&path).unwrap();
+
+    let module = module.map_err(|e| e.print(py)).unwrap();
+    assert_eq!(module.filename_path().unwrap(), path);
+
+    let module = PyModule::new(py, "no_file").unwrap();
+    assert!(module.filename_path().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_module_filename_path_not_utf8() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "not_utf8").unwrap();
+    module
+        .run("import os\n__file__ = os.fsdecode(b'/tmp/caf\\xe9.py')")
+        .unwrap();
+    assert!(module.filename().is_err());
+    assert_eq!(
+        module.filename_path().unwrap(),
+        Path::new(OsStr::from_bytes(b"/tmp/caf\xe9.py"))
+    );
+}
+
+#[test]
+fn test_module_walk() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let root = PyModule::new(py, "walk_root").unwrap();
+    let child = root.ensure_submodule("child").unwrap();
+    let grandchild = child.ensure_submodule("grandchild").unwrap();
+    // Cycles and repeated references must not cause modules to be visited twice.
+    grandchild.add_private("root", root).unwrap();
+    root.add_private("alias", grandchild).unwrap();
+
+    let mut visited = Vec::new();
+    root.walk(|module| {
+        visited.push(module.name()?.to_string());
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(
+        visited,
+        vec!["walk_root", "walk_root.child", "walk_root.child.grandchild"]
+    );
+
+    let mut count = 0;
+    let err = root
+        .walk(|_| {
+            count += 1;
+            Err(pyo3::exceptions::ValueError::py_err("stop"))
+        })
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_module_new_with_doc() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new_with_doc(py, "documented", "Some documentation.").unwrap();
+    py_assert!(py, module, "module.__name__ == 'documented'");
+    py_assert!(py, module, "module.__doc__ == 'Some documentation.'");
+}
+
+#[test]
+fn test_module_call_slice() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::from_code(
+        py,
+        "def count(*args, **kwargs):\n    return len(args) + kwargs.get('extra', 0)",
+        "call_slice.py",
+        "call_slice",
+    )
+    .unwrap();
+
+    for n in 0..4 {
+        let objects: Vec<PyObject> = (0..n).map(|i| i.to_object(py)).collect();
+        let args: Vec<&PyAny> = objects.iter().map(|obj| obj.as_ref(py)).collect();
+        let count: usize = module
+            .call_slice("count", &args, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(count, n);
+    }
+
+    let one = 1.to_object(py);
+    let kwargs = [("extra", 10)].into_py_dict(py);
+    let count: usize = module
+        .call_slice("count", &[one.as_ref(py)], Some(kwargs))
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(count, 11);
+}
+
+#[test]
+fn test_module_get_or_set_with() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "get_or_set_with").unwrap();
+    let mut calls = 0;
+    for _ in 0..2 {
+        let value = module
+            .get_or_set_with("cached", || {
+                calls += 1;
+                Ok(vec![1, 2, 3])
+            })
+            .unwrap();
+        assert_eq!(value.extract::<Vec<i32>>().unwrap(), vec![1, 2, 3]);
+    }
+    assert_eq!(calls, 1);
+    py_assert!(py, module, "module.cached == [1, 2, 3]");
+    py_assert!(py, module, "not hasattr(module, '__all__')");
+
+    let err = module
+        .get_or_set_with("failed", || -> PyResult<i32> {
+            Err(pyo3::exceptions::ValueError::py_err("init failed"))
+        })
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+    py_assert!(py, module, "not hasattr(module, 'failed')");
+
+    // Errors other than `AttributeError` don't call `init`.
+    module
+        .run("def __getattr__(name):\n    raise KeyError(name)")
+        .unwrap();
+    let err = module
+        .get_or_set_with("broken", || -> PyResult<i32> { panic!("init called") })
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::KeyError>(py));
+}
+
+#[test]
+fn test_module_remove() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "remove").unwrap();
+    module.add("kept", 1).unwrap();
+    module.add("removed", 2).unwrap();
+
+    assert!(module.remove("removed").unwrap());
+    py_assert!(py, module, "'removed' not in module.__dict__");
+    py_assert!(py, module, "module.__all__ == ['kept']");
+
+    assert!(!module.remove("removed").unwrap());
+    assert!(!module.remove("never_added").unwrap());
+    py_assert!(py, module, "module.__all__ == ['kept']");
+
+    let module = PyModule::from_code(
+        py,
+        r#"
+import sys, types
+
+class _Module(types.ModuleType):
+    def __delattr__(self, name):
+        raise TypeError('read-only module')
+
+sys.modules[__name__].__class__ = _Module
+__all__ = ['locked']
+locked = 1
+"#,
+        "remove_locked.py",
+        "remove_locked",
+    )
+    .unwrap();
+    let err = module.remove("locked").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+    py_assert!(py, module, "module.__all__ == ['locked']");
+}
+
+#[test]
+fn test_module_add_into() {
+    /// Can only be moved into Python, as it doesn't implement `ToPyObject`.
+    struct Payload(Vec<u8>);
+
+    impl IntoPy<PyObject> for Payload {
+        fn into_py(self, py: Python) -> PyObject {
+            pyo3::types::PyBytes::new(py, &self.0).into()
+        }
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_into").unwrap();
+    module
+        .add_into("payload", Payload(b"data".to_vec()))
+        .unwrap();
+
+    py_assert!(py, module, "module.payload == b'data'");
+    py_assert!(py, module, "module.__all__ == ['payload']");
+}
+
+#[test]
+fn test_module_get_source() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let source = "def answer():\n    return 42\n";
+    let file_name = "<pyo3 get_source>";
+    let module = PyModule::from_code(py, source, file_name, "get_source").unwrap();
+
+    // The file doesn't exist, so there is no source until it is registered with `linecache`.
+    assert_eq!(module.get_source().unwrap(), None);
+
+    let lines: Vec<&str> = vec!["def answer():\n", "    return 42\n"];
+    let entry = (source.len(), py.None(), lines, file_name);
+    py.import("linecache")
+        .unwrap()
+        .get("cache")
+        .unwrap()
+        .set_item(file_name, entry)
+        .unwrap();
+    assert_eq!(module.get_source().unwrap(), Some(source.to_string()));
+
+    let sys = PyModule::import(py, "sys").unwrap();
+    assert_eq!(sys.get_source().unwrap(), None);
+}
+
+#[test]
+fn test_module_add_class_opt() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "add_class_opt").unwrap();
+    module.add_class_opt::<ValueClass>(true).unwrap();
+    module.add_class_opt::<AnonClass>(false).unwrap();
+
+    py_assert!(
+        py,
+        module,
+        "module.ValueClass(3).__class__ is module.ValueClass"
+    );
+    py_assert!(py, module, "module.AnonClass.__name__ == 'AnonClass'");
+    py_assert!(py, module, "module.__all__ == ['ValueClass']");
+}
+
+#[test]
+fn test_module_builder() {
+    use pyo3::types::ModuleBuilder;
+    use pyo3::{wrap_pyfunction, AsPyPointer};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "builder").unwrap();
+    let built = ModuleBuilder::new(module)
+        .class::<ValueClass>()
+        .unwrap()
+        .function(&wrap_pyfunction!(double))
+        .unwrap()
+        .constant("ANSWER", 42)
+        .unwrap()
+        .submodule("sub", |sub| {
+            sub.constant("NAME", "sub")?;
+            Ok(())
+        })
+        .unwrap()
+        .finish();
+    assert_eq!(built.as_ptr(), module.as_ptr());
+
+    py_assert!(
+        py,
+        module,
+        "isinstance(module.ValueClass(1), module.ValueClass)"
+    );
+    py_assert!(py, module, "module.double(2) == 4");
+    py_assert!(py, module, "module.ANSWER == 42");
+    py_assert!(py, module, "module.sub.NAME == 'sub'");
+    py_assert!(
+        py,
+        module,
+        "module.__all__ == ['ValueClass', 'double', 'ANSWER', 'sub']"
+    );
+}
+
+#[test]
+fn test_module_get_raw() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::from_code(
+        py,
+        r#"
+import sys, types
+
+class _Module(types.ModuleType):
+    @property
+    def value(self):
+        return 'computed'
+
+sys.modules[__name__].__class__ = _Module
+globals()['value'] = 'raw'
+"#,
+        "get_raw.py",
+        "get_raw",
+    )
+    .unwrap();
+
+    let value: &str = module.get("value").unwrap().extract().unwrap();
+    assert_eq!(value, "computed");
+    let raw: &str = module.get_raw("value").unwrap().unwrap().extract().unwrap();
+    assert_eq!(raw, "raw");
+    assert!(module.get_raw("missing").unwrap().is_none());
+
+    let module = PyModule::from_code(
+        py,
+        r#"
+class _Key(str):
+    __hash__ = str.__hash__
+
+    def __eq__(self, other):
+        raise RuntimeError('no comparison')
+
+globals()[_Key('clash')] = 1
+"#,
+        "get_raw_error.py",
+        "get_raw_error",
+    )
+    .unwrap();
+    let err = module.get_raw("clash").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::RuntimeError>(py));
+}
+
+#[pymodule]
+fn failing_module(_py: Python, _module: &PyModule) -> PyResult<()> {
+    Err(pyo3::exceptions::ValueError::py_err("init failed"))
+}
+
+#[test]
+fn test_module_init_error() {
+    use pyo3::ffi;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    // Call the init function the way an import does: with the GIL held, but without any
+    // GIL state of our own.
+    let raised_value_error = py.allow_threads(|| unsafe {
+        let state = ffi::PyGILState_Ensure();
+        let module = PyInit_failing_module();
+        let raised = module.is_null() && ffi::PyErr_ExceptionMatches(ffi::PyExc_ValueError) != 0;
+        ffi::PyErr_Clear();
+        ffi::PyGILState_Release(state);
+        raised
+    });
+    assert!(raised_value_error);
+}
+
+#[test]
+fn test_module_add_invalid_index() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "invalid_index").unwrap();
+    module.setattr("__all__", ("fixed",)).unwrap();
+    let err = module.add("value", 1).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+    py_assert!(py, module, "not hasattr(module, 'value')");
+}